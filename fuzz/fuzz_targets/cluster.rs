@@ -0,0 +1,21 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use zim::Cluster;
+
+// Parses the input as a single cluster, whose first byte is the info byte. Any input has to
+// result in either a cluster or an error, never a panic.
+fuzz_target!(|data: &[u8]| {
+    for version in [5, 6] {
+        let cluster = match Cluster::new(data, &[0], 0, data.len() as u64, version) {
+            Ok(cluster) => cluster,
+            Err(_) => continue,
+        };
+        let _ = cluster.decompressed_size();
+        for idx in 0..8 {
+            let _ = cluster.get_blob_size(idx);
+            let _ = cluster.get_blob(idx).map(|blob| blob.len());
+            let _ = cluster.blob_file_range(idx);
+        }
+    }
+});
@@ -8,7 +8,7 @@ use clap::Parser;
 use indicatif::{ProgressBar, ProgressStyle};
 use rayon::prelude::*;
 use stopwatch::Stopwatch;
-use zim::{Cluster, DirectoryEntry, MimeType, Namespace, Target, Zim};
+use zim::{escape_path_segment, Cluster, DirectoryEntry, MimeType, Namespace, Target, Zim};
 
 /// Extract zim files into their on disk structure.
 #[derive(Parser, Debug)]
@@ -23,6 +23,9 @@ struct Args {
     /// Write files to disk, instead of using hard links
     #[arg(long, default_value_t = false)]
     flatten_link: bool,
+    /// Replace slashes in URLs with `%2F`, instead of creating subdirectories
+    #[arg(long, default_value_t = false)]
+    flatten_slashes: bool,
     #[arg(required = true)]
     input: String,
 }
@@ -32,6 +35,7 @@ fn main() {
 
     let skip_link = args.skip_link;
     let flatten_link = args.flatten_link;
+    let nested = !args.flatten_slashes;
     let out = args.out.unwrap_or_else(|| "out".to_string());
     let root_output = Path::new(&out);
 
@@ -83,7 +87,7 @@ fn main() {
             false
         })
         .for_each(|entry| {
-            process_file(root_output, &cluster_map, entry, &pb);
+            process_file(root_output, &cluster_map, entry, nested, &pb);
         });
 
     if !skip_link {
@@ -97,7 +101,15 @@ fn main() {
                 false
             })
             .for_each(|entry| {
-                process_link(&zim_file, root_output, entry, skip_link, flatten_link, &pb);
+                process_link(
+                    &zim_file,
+                    root_output,
+                    entry,
+                    skip_link,
+                    flatten_link,
+                    nested,
+                    &pb,
+                );
             });
     }
 
@@ -148,9 +160,16 @@ fn process_file<'a>(
     root_output: &Path,
     cluster_map: &'a HashMap<u32, Cluster<'a>>,
     entry: &DirectoryEntry,
+    nested: bool,
     pb: &ProgressBar,
 ) {
-    let dst = make_path(root_output, entry.namespace, &entry.url, &entry.mime_type);
+    let dst = make_path(
+        root_output,
+        entry.namespace,
+        &entry.url,
+        &entry.mime_type,
+        nested,
+    );
     match entry.target.as_ref() {
         Some(Target::Cluster(cluster_index, blob_idx)) => {
             let cluster = cluster_map.get(cluster_index).expect("missing cluster");
@@ -177,9 +196,16 @@ fn process_link(
     entry: &DirectoryEntry,
     skip_link: bool,
     flatten_link: bool,
+    nested: bool,
     pb: &ProgressBar,
 ) {
-    let dst = make_path(root_output, entry.namespace, &entry.url, &entry.mime_type);
+    let dst = make_path(
+        root_output,
+        entry.namespace,
+        &entry.url,
+        &entry.mime_type,
+        nested,
+    );
 
     if entry.target.is_none() {
         eprintln!("skipping missing target {:?} {:?}", dst, entry);
@@ -196,7 +222,13 @@ fn process_link(
                         .get_by_url_index(*redir)
                         .expect("failed to get_by_url_index")
                 };
-                let src = make_path(root_output, entry.namespace, &entry.url, &entry.mime_type);
+                let src = make_path(
+                    root_output,
+                    entry.namespace,
+                    &entry.url,
+                    &entry.mime_type,
+                    nested,
+                );
                 make_link(src, dst, flatten_link);
                 pb.inc(1);
             }
@@ -251,16 +283,27 @@ fn ignore_exists_err<T: AsRef<str>>(e: std::io::Error, msg: T) {
     }
 }
 
-fn make_path(root: &Path, namespace: Namespace, url: &str, mime_type: &MimeType) -> PathBuf {
-    let mut s = String::new();
-    s.push(namespace as u8 as char);
-    let mut path = if url.starts_with('/') {
-        // make absolute urls relative to the output folder
-        let url = url.replacen('/', "", 1);
-        root.join(&s).join(url)
+/// Builds the output path of an entry.
+///
+/// With `nested`, slashes in the URL create subdirectories, otherwise they are replaced by `%2F`.
+/// URLs come from the archive and can't be trusted, so `.` and `..` segments are escaped as well,
+/// and the result always lies within `root`.
+fn make_path(
+    root: &Path,
+    namespace: Namespace,
+    url: &str,
+    mime_type: &MimeType,
+    nested: bool,
+) -> PathBuf {
+    let mut path = root.join((namespace as u8 as char).to_string());
+    if nested {
+        // empty segments, e.g. from absolute urls, are skipped
+        for segment in url.split('/').filter(|segment| !segment.is_empty()) {
+            path.push(escape_path_segment(segment));
+        }
     } else {
-        root.join(&s).join(url)
-    };
+        path.push(escape_path_segment(&url.replace('/', "%2F")));
+    }
 
     if let MimeType::Type(typ) = mime_type {
         let extension = match typ.as_str() {
@@ -290,3 +333,57 @@ fn make_path(root: &Path, namespace: Namespace, url: &str, mime_type: &MimeType)
 
     path
 }
+
+#[cfg(test)]
+mod tests {
+    use std::path::Component;
+
+    use super::*;
+
+    #[test]
+    fn test_make_path() {
+        let root = Path::new("out");
+        let html = MimeType::Type("text/html".to_string());
+        let png = MimeType::Type("image/png".to_string());
+
+        assert_eq!(
+            make_path(root, Namespace::Articles, "Foo/Bar", &html, true),
+            Path::new("out/A/Foo/Bar.html")
+        );
+        assert_eq!(
+            make_path(root, Namespace::Articles, "Foo/Bar", &html, false),
+            Path::new("out/A/Foo%2FBar.html")
+        );
+        assert_eq!(
+            make_path(root, Namespace::ImagesFile, "/logo.png", &png, true),
+            Path::new("out/I/logo.png")
+        );
+    }
+
+    #[test]
+    fn test_make_path_stays_in_root() {
+        let root = Path::new("out");
+        let urls = [
+            "../../etc/passwd",
+            "..",
+            "a/../../..",
+            "/../x",
+            "./.",
+            "..\\..\\x",
+        ];
+        for url in urls {
+            for nested in [true, false] {
+                let path = make_path(root, Namespace::Articles, url, &MimeType::Redirect, nested);
+                let relative = path.strip_prefix("out/A").unwrap();
+                assert!(
+                    relative
+                        .components()
+                        .all(|c| matches!(c, Component::Normal(_))),
+                    "{} -> {}",
+                    url,
+                    path.display()
+                );
+            }
+        }
+    }
+}
@@ -54,9 +54,9 @@ fn main() {
     p1.show_message = true;
     p1.message("Building cluster map :");
 
-    for i in zim.iterate_by_urls() {
-        if let Some(Target::Cluster(cid, _)) = i.target {
-            cluster_map.entry(cid).or_default().push(i);
+    for idx in 0..zim.header.article_count {
+        if let Ok(Some(Target::Cluster(cid, _))) = zim.target_at(idx) {
+            cluster_map.entry(cid).or_default().push(idx);
         }
         p1.inc();
     }
@@ -71,9 +71,7 @@ fn main() {
     // link all redirects
     for entry in zim.iterate_by_urls() {
         // get redirect entry
-        if let Some(Target::Redirect(redir)) = entry.target {
-            let redir = zim.get_by_url_index(redir).unwrap();
-
+        if let Some(redir) = zim.redirect_target(&entry).unwrap() {
             let mut s = String::new();
             s.push(redir.namespace as u8 as char);
             let src = root_output.join(&s).join(&redir.url);
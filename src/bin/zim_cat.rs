@@ -0,0 +1,42 @@
+use std::io::Write;
+use std::process::exit;
+
+use clap::Parser;
+use zim::{Result, Zim};
+
+/// Print the content of a single entry of a zim file
+#[derive(Parser, Debug)]
+#[command(author, version, about, long_about = None)]
+struct Args {
+    /// The zim file to read
+    input: String,
+    /// The entry to print, as namespace/url, e.g. A/Index
+    path: String,
+}
+
+fn main() -> Result<()> {
+    let args = Args::parse();
+
+    let (ns, url) = match args.path.split_once('/') {
+        Some((ns, url)) if ns.chars().count() == 1 => (ns.chars().next().unwrap(), url),
+        _ => {
+            eprintln!("Invalid path {}, expected namespace/url", args.path);
+            exit(2);
+        }
+    };
+
+    let zim_file = Zim::new(&args.input)?;
+    match zim_file.content_by_url(ns, url)? {
+        Some(content) => {
+            let mut stdout = std::io::stdout().lock();
+            stdout.write_all(&content)?;
+            stdout.flush()?;
+        }
+        None => {
+            eprintln!("Entry {} not found", args.path);
+            exit(1);
+        }
+    }
+
+    Ok(())
+}
@@ -10,6 +10,9 @@ use zim::{Result, Zim};
 struct Args {
     /// The zim file to inspect
     input: String,
+    /// Decompress all clusters to report the space savings of compression
+    #[arg(long, default_value_t = false)]
+    space_savings: bool,
 }
 
 fn main() -> Result<()> {
@@ -30,6 +33,11 @@ fn main() -> Result<()> {
         "Article Count: {}",
         zim_file.article_count().to_formatted_string(&Locale::en)
     );
+    println!(
+        "Redirects: {}, Content Entries: {}",
+        zim_file.redirect_count()?.to_formatted_string(&Locale::en),
+        zim_file.content_count()?.to_formatted_string(&Locale::en)
+    );
     println!(
         "Mime List Pos: {}",
         zim_file
@@ -65,12 +73,36 @@ fn main() -> Result<()> {
             .to_formatted_string(&Locale::en)
     );
 
+    println!(
+        "File Size: {}",
+        zim_file.file_size().to_formatted_string(&Locale::en)
+    );
+    println!(
+        "Content Size: {}",
+        zim_file.content_size().to_formatted_string(&Locale::en)
+    );
+    if args.space_savings {
+        let decompressed = zim_file.decompressed_size_estimate()?;
+        let saved = decompressed.saturating_sub(zim_file.content_size());
+        println!(
+            "Decompressed Size: {} (compression saves {}, {:.1}%)",
+            decompressed.to_formatted_string(&Locale::en),
+            saved.to_formatted_string(&Locale::en),
+            saved as f64 * 100. / decompressed.max(1) as f64
+        );
+    }
+
     let mut compressions = HashSet::new();
-    for cluster_id in 0..zim_file.header.cluster_count {
-        let cluster = zim_file.get_cluster(cluster_id)?;
-        compressions.insert(cluster.compression());
+    for cluster in zim_file.clusters() {
+        compressions.insert(cluster?.compression());
     }
     println!("Compressions: {:?}", compressions);
+    for compression in compressions.iter().filter(|c| !c.is_supported()) {
+        println!(
+            "Warning: contains {:?} clusters, which are not supported yet",
+            compression
+        );
+    }
 
     let (main_page, main_page_idx) = if let Some(main_page_idx) = zim_file.header.main_page {
         let page = zim_file.get_by_url_index(main_page_idx)?;
@@ -0,0 +1,56 @@
+use std::ops::Range;
+
+/// The offsets of the blobs within a cluster.
+///
+/// A cluster with `n` blobs stores `n + 1` offsets: the start of every blob, followed by the end
+/// of the last one. Offsets are relative to the start of the (decompressed) cluster data, after
+/// the info byte.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlobList(Vec<u64>);
+
+impl BlobList {
+    pub fn new(offsets: Vec<u64>) -> Self {
+        BlobList(offsets)
+    }
+
+    /// Returns the number of blobs.
+    pub fn count(&self) -> usize {
+        self.0.len().saturating_sub(1)
+    }
+
+    /// Returns the raw offsets.
+    pub fn offsets(&self) -> &[u64] {
+        &self.0
+    }
+
+    /// Returns the byte range of blob `idx`, or `None` if `idx` is out of bounds.
+    pub fn range(&self, idx: u32) -> Option<Range<usize>> {
+        let idx = idx as usize;
+        let start = *self.0.get(idx)? as usize;
+        let end = *self.0.get(idx + 1)? as usize;
+        if end < start {
+            return None;
+        }
+
+        Some(start..end)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_range() {
+        let list = BlobList::new(vec![12, 15, 15, 20]);
+        assert_eq!(list.range(0), Some(12..15));
+        assert_eq!(list.range(1), Some(15..15));
+        assert_eq!(list.range(2), Some(15..20));
+        assert_eq!(list.range(3), None);
+        assert_eq!(list.count(), 3);
+
+        assert_eq!(BlobList::new(vec![4]).range(0), None);
+        assert_eq!(BlobList::new(vec![]).count(), 0);
+        assert_eq!(BlobList::new(vec![8, 4]).range(0), None);
+    }
+}
@@ -0,0 +1,172 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+/// Default memory budget for decompressed clusters: 64 MiB.
+pub const DEFAULT_CACHE_SIZE: usize = 64 * 1024 * 1024;
+
+/// A least-recently-used cache of decompressed cluster data, keyed by cluster index.
+///
+/// The cache is bounded by the total size of the buffers it holds. Buffers are shared through an
+/// `Arc`, so evicting an entry never invalidates a `Cluster` that is still using it.
+///
+/// The cache also counts lookups and decompressions, see `CacheStats`.
+#[derive(Debug)]
+pub struct ClusterCache {
+    capacity: usize,
+    inner: Mutex<CacheInner>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+    clusters_decompressed: AtomicU64,
+    bytes_decompressed: AtomicU64,
+}
+
+/// Counters of a `ClusterCache`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub clusters_decompressed: u64,
+    pub bytes_decompressed: u64,
+}
+
+#[derive(Debug, Default)]
+struct CacheInner {
+    size: usize,
+    map: HashMap<u32, Arc<Vec<u8>>>,
+    order: VecDeque<u32>,
+}
+
+impl ClusterCache {
+    /// Creates a cache holding at most `capacity` bytes of decompressed data.
+    pub fn new(capacity: usize) -> Self {
+        ClusterCache {
+            capacity,
+            inner: Mutex::new(CacheInner::default()),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+            clusters_decompressed: AtomicU64::new(0),
+            bytes_decompressed: AtomicU64::new(0),
+        }
+    }
+
+    /// Returns the maximum number of bytes of decompressed data held.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Returns the decompressed data of cluster `idx`, if cached.
+    pub fn get(&self, idx: u32) -> Option<Arc<Vec<u8>>> {
+        let mut inner = self.inner.lock().unwrap();
+        let data = match inner.map.get(&idx).cloned() {
+            Some(data) => data,
+            None => {
+                self.misses.fetch_add(1, Ordering::Relaxed);
+                return None;
+            }
+        };
+        inner.touch(idx);
+        self.hits.fetch_add(1, Ordering::Relaxed);
+
+        Some(data)
+    }
+
+    /// Records that a cluster was decompressed into `len` bytes.
+    pub fn record_decompression(&self, len: usize) {
+        self.clusters_decompressed.fetch_add(1, Ordering::Relaxed);
+        self.bytes_decompressed
+            .fetch_add(len as u64, Ordering::Relaxed);
+    }
+
+    /// Returns the counters collected so far.
+    pub fn stats(&self) -> CacheStats {
+        CacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            clusters_decompressed: self.clusters_decompressed.load(Ordering::Relaxed),
+            bytes_decompressed: self.bytes_decompressed.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Stores the decompressed data of cluster `idx`, evicting the least recently used entries
+    /// until the cache fits its budget again.
+    pub fn insert(&self, idx: u32, data: Arc<Vec<u8>>) {
+        if data.len() > self.capacity {
+            return;
+        }
+
+        let mut inner = self.inner.lock().unwrap();
+        if let Some(old) = inner.map.insert(idx, data.clone()) {
+            inner.size -= old.len();
+        }
+        inner.size += data.len();
+        inner.touch(idx);
+
+        while inner.size > self.capacity {
+            match inner.order.pop_front() {
+                Some(evict) => {
+                    if let Some(old) = inner.map.remove(&evict) {
+                        inner.size -= old.len();
+                    }
+                }
+                None => break,
+            }
+        }
+    }
+
+    /// Drops all cached data. The counters are kept.
+    pub fn clear(&self) {
+        *self.inner.lock().unwrap() = CacheInner::default();
+    }
+
+    /// Number of cached clusters.
+    #[cfg(test)]
+    pub fn len(&self) -> usize {
+        self.inner.lock().unwrap().map.len()
+    }
+
+    /// Total size in bytes of the cached data.
+    #[cfg(test)]
+    pub fn size(&self) -> usize {
+        self.inner.lock().unwrap().size
+    }
+}
+
+impl CacheInner {
+    /// Marks `idx` as most recently used.
+    fn touch(&mut self, idx: u32) {
+        if let Some(pos) = self.order.iter().position(|i| *i == idx) {
+            self.order.remove(pos);
+        }
+        self.order.push_back(idx);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_evicts_least_recently_used() {
+        let cache = ClusterCache::new(10);
+        cache.insert(0, Arc::new(vec![0; 4]));
+        cache.insert(1, Arc::new(vec![1; 4]));
+        assert!(cache.get(0).is_some());
+
+        cache.insert(2, Arc::new(vec![2; 4]));
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.size(), 8);
+        assert!(cache.get(0).is_some());
+        assert!(cache.get(1).is_none());
+        assert!(cache.get(2).is_some());
+
+        // too large to ever fit
+        cache.insert(3, Arc::new(vec![3; 11]));
+        assert!(cache.get(3).is_none());
+
+        cache.clear();
+        assert_eq!(cache.len(), 0);
+        assert_eq!(cache.size(), 0);
+        assert!(cache.get(0).is_none());
+    }
+}
@@ -0,0 +1,127 @@
+#[cfg(feature = "mmap")]
+use std::path::Path;
+
+use crate::cluster::Cluster;
+use crate::directory_entry::DirectoryEntry;
+use crate::errors::{Error, Result};
+use crate::zim::Zim;
+
+/// A `Zim` that validates every access, for reading untrusted archives.
+///
+/// `Zim` trusts the offsets stored in the archive and may panic when they are wrong.
+/// `CheckedZim` checks indices, entry offsets and cluster bounds before using them, so a broken
+/// or malicious archive results in an `Error` instead. The checks happen lazily on each access.
+pub struct CheckedZim(Zim);
+
+impl CheckedZim {
+    /// Loads a Zim file, see `Zim::new`.
+    #[cfg(feature = "mmap")]
+    pub fn new<P: AsRef<Path>>(p: P) -> Result<CheckedZim> {
+        Ok(CheckedZim(Zim::new(p)?))
+    }
+
+    /// Loads a Zim archive held in memory, see `Zim::from_bytes`.
+    pub fn from_bytes(data: Vec<u8>) -> Result<CheckedZim> {
+        Ok(CheckedZim(Zim::from_bytes(data)?))
+    }
+
+    /// The unchecked archive.
+    pub fn inner(&self) -> &Zim {
+        &self.0
+    }
+
+    /// Returns the `DirectoryEntry` found at the given URL index.
+    pub fn read_entry(&self, idx: u32) -> Result<DirectoryEntry> {
+        let offset = *self
+            .0
+            .url_list
+            .get(idx as usize)
+            .ok_or(Error::OutOfBounds)?;
+        let view = usize::try_from(offset)
+            .ok()
+            .and_then(|offset| self.0.master_view.get(offset..))
+            .ok_or(Error::OutOfBounds)?;
+
+        DirectoryEntry::new(&self.0, view)
+    }
+
+    /// Returns the given `Cluster`, after checking it lies within the file.
+    pub fn get_cluster(&self, idx: u32) -> Result<Cluster<'_>> {
+        let list = &self.0.cluster_list;
+        let start = *list.get(idx as usize).ok_or(Error::OutOfBounds)?;
+        let end = match list.get(idx as usize + 1) {
+            Some(end) => *end,
+            None => self.0.header.checksum_pos,
+        };
+        if start >= end || end > self.0.master_view.len() as u64 {
+            return Err(Error::OutOfBounds);
+        }
+
+        let cluster = self.0.get_cluster(idx)?;
+        if !cluster.compression().is_supported() {
+            return Err(Error::UnsupportedCompression(cluster.compression()));
+        }
+
+        Ok(cluster)
+    }
+
+    /// Returns a copy of blob `blob` of cluster `cluster`.
+    pub fn get_blob(&self, cluster: u32, blob: u32) -> Result<Vec<u8>> {
+        let cluster = self.get_cluster(cluster)?;
+        let blob = cluster.get_blob(blob)?;
+
+        Ok(blob.to_vec())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    use super::*;
+    use crate::target::Target;
+    use crate::test_util;
+
+    /// Reads everything reachable through the checked API.
+    fn read_all(zim: &CheckedZim) {
+        for idx in 0..zim.inner().header.article_count {
+            if let Ok(entry) = zim.read_entry(idx) {
+                if let Some(Target::Cluster(cluster, blob)) = entry.target {
+                    let _ = zim.get_blob(cluster, blob);
+                }
+            }
+        }
+        for idx in 0..zim.inner().header.cluster_count + 1 {
+            let _ = zim.get_blob(idx, 0);
+        }
+    }
+
+    #[test]
+    fn test_valid_archive() {
+        let zim = CheckedZim::from_bytes(test_util::sample(5).build()).unwrap();
+        let entry = zim.read_entry(0).unwrap();
+        assert_eq!(entry.url, "Berlin");
+        assert_eq!(zim.get_blob(0, 0).unwrap(), b"<html>Berlin</html>");
+        assert!(matches!(zim.read_entry(100), Err(Error::OutOfBounds)));
+        assert!(matches!(zim.get_blob(9, 0), Err(Error::OutOfBounds)));
+        assert!(matches!(zim.get_blob(0, 9), Err(Error::OutOfBounds)));
+    }
+
+    #[test]
+    fn test_mutations_never_panic() {
+        let original = test_util::sample(5).build();
+        let mut rng = StdRng::seed_from_u64(7);
+
+        for _ in 0..2000 {
+            let mut data = original.clone();
+            for _ in 0..rng.gen_range(1..8) {
+                let pos = rng.gen_range(0..data.len());
+                data[pos] = rng.gen();
+            }
+            if let Ok(zim) = CheckedZim::from_bytes(data) {
+                read_all(&zim);
+            }
+        }
+    }
+}
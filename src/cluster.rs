@@ -1,15 +1,16 @@
 use std::fmt;
-use std::io::Cursor;
-use std::io::Read;
-use std::ops::Deref;
-use std::sync::{Arc, RwLock};
+use std::io::{self, Cursor, Read};
+use std::ops::{Deref, Range};
+use std::str::FromStr;
+use std::sync::{Arc, PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard};
 
 use bitreader::BitReader;
 use byteorder::{LittleEndian, ReadBytesExt};
-use memmap::Mmap;
 use ouroboros::self_referencing;
 use xz2::read::XzDecoder;
 
+use crate::blob_list::BlobList;
+use crate::cache::ClusterCache;
 use crate::errors::{Error, Result};
 
 #[repr(u8)]
@@ -40,12 +41,68 @@ impl Compression {
             _ => Err(Error::UnknownCompression(raw)),
         }
     }
+
+    /// A rough estimate of how many times larger the data gets when decompressed.
+    ///
+    /// This is a heuristic based on typical archive content, useful to budget memory before
+    /// decompressing. The actual ratio varies a lot between clusters.
+    pub fn typical_expansion(&self) -> u32 {
+        match self {
+            Compression::None => 1,
+            Compression::Zlib | Compression::Zstd => 3,
+            Compression::Bzip2 | Compression::Lzma2 => 4,
+        }
+    }
+
+    /// Returns `true` if clusters using this compression can be decompressed.
+    ///
+    /// Zlib and Bzip2 are part of the format but not implemented yet.
+    pub fn is_supported(&self) -> bool {
+        match self {
+            Compression::None | Compression::Lzma2 | Compression::Zstd => true,
+            Compression::Zlib | Compression::Bzip2 => false,
+        }
+    }
+}
+
+impl fmt::Display for Compression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Compression::None => "none",
+            Compression::Zlib => "zlib",
+            Compression::Bzip2 => "bzip2",
+            Compression::Lzma2 => "lzma2",
+            Compression::Zstd => "zstd",
+        };
+        f.write_str(name)
+    }
+}
+
+impl FromStr for Compression {
+    type Err = Error;
+
+    /// Parses a compression name as written by `Display`, ignoring case. `xz` is accepted as an
+    /// alias for `lzma2`.
+    fn from_str(s: &str) -> Result<Compression> {
+        match s.to_ascii_lowercase().as_str() {
+            "none" => Ok(Compression::None),
+            "zlib" => Ok(Compression::Zlib),
+            "bzip2" => Ok(Compression::Bzip2),
+            "lzma2" | "xz" => Ok(Compression::Lzma2),
+            "zstd" => Ok(Compression::Zstd),
+            _ => Err(Error::UnknownCompressionName(s.to_string())),
+        }
+    }
 }
 
 /// A cluster of blobs
 ///
 /// Within an ZIM archive, clusters contain several blobs of data that are all compressed together.
 /// Each blob is the data for an article.
+///
+/// A `Cluster` is a cheap handle that is `Send + Sync` and can be cloned into other threads.
+/// Decompression happens at most once, behind a lock, and is shared by all clones. If a thread
+/// panics while holding the lock, later accesses return `Error::LockPoisoned`.
 #[derive(Clone)]
 pub struct Cluster<'a>(Arc<RwLock<InnerCluster<'a>>>);
 
@@ -56,13 +113,15 @@ pub struct InnerCluster<'a> {
     end: u64,
     size: u64,
     view: &'a [u8],
-    blob_list: Option<Vec<u64>>, // offsets into data
-    decompressed: Option<Vec<u8>>,
+    blob_list: Option<BlobList>,
+    decompressed: Option<Arc<Vec<u8>>>,
+    idx: u32,
+    cache: Option<&'a ClusterCache>,
 }
 
 impl<'a> fmt::Debug for Cluster<'a> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let raw = self.0.read().unwrap();
+        let raw = self.0.read().unwrap_or_else(PoisonError::into_inner);
         f.debug_struct("Cluster")
             .field("extended", &raw.extended)
             .field("compression", &raw.compression)
@@ -81,8 +140,8 @@ impl<'a> fmt::Debug for Cluster<'a> {
 
 impl<'a> Cluster<'a> {
     pub fn new(
-        master_view: &'a Mmap,
-        cluster_list: &'a Vec<u64>,
+        master_view: &'a [u8],
+        cluster_list: &'a [u64],
         idx: u32,
         checksum_pos: u64,
         version: u16,
@@ -93,41 +152,260 @@ impl<'a> Cluster<'a> {
             idx,
             checksum_pos,
             version,
+            None,
         )?))))
     }
 
+    /// Like `new`, but shares decompressed data through the given cache.
+    pub(crate) fn with_cache(
+        master_view: &'a [u8],
+        cluster_list: &'a [u64],
+        idx: u32,
+        checksum_pos: u64,
+        version: u16,
+        cache: &'a ClusterCache,
+    ) -> Result<Cluster<'a>> {
+        Ok(Cluster(Arc::new(RwLock::new(InnerCluster::new(
+            master_view,
+            cluster_list,
+            idx,
+            checksum_pos,
+            version,
+            Some(cache),
+        )?))))
+    }
+
+    /// Locks the cluster for reading, failing if another thread panicked while holding the lock.
+    fn read(&self) -> Result<RwLockReadGuard<'_, InnerCluster<'a>>> {
+        self.0.read().map_err(|_| Error::LockPoisoned)
+    }
+
+    /// Locks the cluster for writing, failing if another thread panicked while holding the lock.
+    fn write(&self) -> Result<RwLockWriteGuard<'_, InnerCluster<'a>>> {
+        self.0.write().map_err(|_| Error::LockPoisoned)
+    }
+
     pub fn decompress(&self) -> Result<()> {
-        self.0.write().unwrap().decompress()
+        self.write()?.decompress()
+    }
+
+    /// Decompresses as much of a damaged cluster as possible, returning how many blobs, counted
+    /// from the first one, are complete.
+    ///
+    /// If the compressed data is truncated or corrupt part way, the blobs decoded before that
+    /// point can still be read with `get_blob`, later ones return `Error::OutOfBounds`. Only the
+    /// blob offset table at the start must be intact. The partial data isn't added to the
+    /// cluster cache. For intact clusters this is the same as `decompress`.
+    pub fn decompress_partial(&self) -> Result<usize> {
+        self.write()?.decompress_partial()
+    }
+
+    /// Decompresses the cluster data into `buf`, replacing its contents.
+    ///
+    /// This bypasses the cluster cache and the data kept by this `Cluster`, so a single buffer
+    /// can be reused for many clusters without allocating for each of them.
+    pub fn decompress_into(&self, buf: &mut Vec<u8>) -> Result<()> {
+        self.read()?.decode_into(buf)
+    }
+
+    /// Returns a copy of the whole decompressed cluster data, excluding the info byte.
+    ///
+    /// This is the blob offset table followed by all blobs, which can be large. For uncompressed
+    /// clusters this is the data as stored in the file.
+    pub fn decompressed(&self) -> Result<Vec<u8>> {
+        self.decompress()?;
+        let lock = self.read()?;
+        Ok(match lock.decompressed {
+            Some(ref data) => data.to_vec(),
+            None => lock.view[1..].to_vec(),
+        })
+    }
+
+    /// Size in bytes of the cluster data after decompression, excluding the info byte.
+    ///
+    /// Decompresses the cluster if needed.
+    pub fn decompressed_size(&self) -> Result<u64> {
+        self.decompress()?;
+        let lock = self.read()?;
+        Ok(match lock.decompressed {
+            Some(ref data) => data.len() as u64,
+            None => lock.size - 1,
+        })
+    }
+
+    /// Returns the info byte the cluster starts with, including the reserved high bits.
+    pub fn info_byte(&self) -> u8 {
+        // the view is never empty, see `InnerCluster::new`
+        self.0.read().unwrap_or_else(PoisonError::into_inner).view[0]
+    }
+
+    /// Returns `true` if the blob offsets of this cluster are 8 bytes wide instead of 4.
+    pub fn is_extended(&self) -> bool {
+        self.0
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .extended
     }
 
     pub fn compression(&self) -> Compression {
-        self.0.read().unwrap().compression
+        // the compression never changes, so a poisoned lock doesn't matter
+        self.0
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .compression
     }
 
     /// Returns the size in bytes of the blob with index `idx`.
     ///
     /// Returns `None` if `idx` is out of bounds.
     pub fn get_blob_size(&self, idx: u32) -> Option<usize> {
-        let lock = self.0.read().unwrap();
-        let list = lock.blob_list.as_ref()?;
-        
-        let start = *list.get(idx as usize)? as usize;
-        let end = *list.get(idx as usize + 1).unwrap_or(&lock.size) as usize;
+        let lock = self.read().ok()?;
+
+        lock.blob_list.as_ref()?.range(idx).map(|range| range.len())
+    }
+
+    /// Decompresses the cluster once and returns a reader for all of its blobs.
+    ///
+    /// Unlike `get_blob`, which locks the cluster for every `Blob`, the reader only locks once.
+    pub fn reader(&self) -> Result<ClusterReader<'_>> {
+        self.decompress()?;
+        let lock = self.read()?;
+
+        Ok(ClusterReader {
+            view: lock.view,
+            decompressed: lock.decompressed.clone(),
+            blob_list: lock.blob_list.clone().ok_or(Error::MissingBlobList)?,
+        })
+    }
+
+    /// Returns the absolute byte range of blob `idx` in the archive file.
+    ///
+    /// Only blobs of uncompressed clusters are stored as-is in the file, so these can be served
+    /// straight from the file, e.g. with `sendfile`. Returns `None` for compressed clusters.
+    pub fn blob_file_range(&self, idx: u32) -> Result<Option<Range<u64>>> {
+        let lock = self.read()?;
+        if lock.compression != Compression::None {
+            return Ok(None);
+        }
+
+        let range = lock
+            .blob_list
+            .as_ref()
+            .ok_or(Error::MissingBlobList)?
+            .range(idx)
+            .filter(|range| range.end < lock.view.len())
+            .ok_or(Error::OutOfBounds)?;
+        // blob offsets are relative to the data after the info byte
+        let start = lock.start + 1 + range.start as u64;
+
+        Ok(Some(start..start + range.len() as u64))
+    }
+
+    /// Returns a reader over the data of blob `idx`.
+    ///
+    /// If the cluster is compressed and hasn't been decompressed yet, the data is decompressed
+    /// while reading, and only up to the end of the blob. Neither the whole cluster nor the blob
+    /// is ever held in memory, which suits serving large blobs.
+    pub fn blob_reader(&self, idx: u32) -> Result<BlobReader<'a>> {
+        let lock = self.read()?;
+        let data = lock.decompressed.clone().or_else(|| {
+            lock.cache
+                .filter(|_| lock.compression != Compression::None)
+                .and_then(|cache| cache.get(lock.idx))
+        });
+        let view: &'a [u8] = lock.view;
+
+        let (reader, range): (Box<dyn Read + Send + 'a>, _) = match (lock.compression, data) {
+            (Compression::None, _) => {
+                let range = lock
+                    .blob_list
+                    .as_ref()
+                    .ok_or(Error::MissingBlobList)?
+                    .range(idx)
+                    .ok_or(Error::OutOfBounds)?;
+                (Box::new(&view[1..]), range)
+            }
+            (_, Some(data)) => {
+                let range = match lock.blob_list {
+                    Some(ref list) => list.range(idx),
+                    None => parse_blob_list(&data, lock.extended)?.range(idx),
+                }
+                .ok_or(Error::OutOfBounds)?;
+                (Box::new(Cursor::new(SharedData(data))), range)
+            }
+            (Compression::Lzma2, None) => {
+                let decoder = XzDecoder::new(&view[1..]);
+                stream_blob_range(decoder, idx, lock.extended)?
+            }
+            (Compression::Zstd, None) => {
+                let decoder = zstd::stream::read::Decoder::with_buffer(&view[1..])?;
+                stream_blob_range(decoder, idx, lock.extended)?
+            }
+            (compression @ (Compression::Bzip2 | Compression::Zlib), None) => {
+                return Err(Error::UnsupportedCompression(compression));
+            }
+        };
+
+        // skip to the start of the blob
+        let mut reader = reader;
+        let skip = range.start as u64;
+        if io::copy(&mut (&mut reader).take(skip), &mut io::sink())? != skip {
+            return Err(Error::OutOfBounds);
+        }
+
+        Ok(BlobReader(reader.take(range.len() as u64)))
+    }
+
+    /// Returns the number of blobs in the cluster, decompressing it if needed.
+    pub fn blob_count(&self) -> Result<u32> {
+        {
+            let lock = self.read()?;
+            if !lock.needs_decompression() {
+                let list = lock.blob_list.as_ref().ok_or(Error::MissingBlobList)?;
+                return Ok(list.count() as u32);
+            }
+        }
+
+        self.write()?.decompress()?;
+        let lock = self.read()?;
+        let list = lock.blob_list.as_ref().ok_or(Error::MissingBlobList)?;
 
-        Some(end - start)
+        Ok(list.count() as u32)
+    }
+
+    /// Returns the blob offset table: the start of every blob followed by the end of the last
+    /// one, so one more than `blob_count`.
+    ///
+    /// Offsets are relative to the cluster data after the info byte. The table is stored at the
+    /// start of the cluster data, so compressed clusters are decompressed to read it.
+    pub fn blob_offsets(&self) -> Result<Vec<u64>> {
+        self.blob_count()?;
+        let lock = self.read()?;
+        let list = lock.blob_list.as_ref().ok_or(Error::MissingBlobList)?;
+
+        Ok(list.offsets().to_vec())
+    }
+
+    /// Like `get_blob`, but returns `None` if `idx` is not below `blob_count`.
+    pub fn try_get_blob<'b: 'a>(&'b self, idx: u32) -> Result<Option<Blob<'a, 'b>>> {
+        if idx >= self.blob_count()? {
+            return Ok(None);
+        }
+
+        self.get_blob(idx).map(Some)
     }
 
     pub fn get_blob<'b: 'a>(&'b self, idx: u32) -> Result<Blob<'a, 'b>> {
         {
-            let lock = self.0.read().unwrap();
+            let lock = self.read()?;
             if lock.needs_decompression() {
                 drop(lock);
-                self.0.write().unwrap().decompress()?;
+                self.write()?.decompress()?;
             }
         }
 
         let blob = BlobTryBuilder {
-            guard: self.0.read().unwrap(),
+            guard: self.read()?,
             slice_builder: |guard| guard.get_blob(idx),
         }
         .try_build()?;
@@ -136,6 +414,74 @@ impl<'a> Cluster<'a> {
     }
 }
 
+/// Reads many blobs from a single decompressed cluster.
+///
+/// Created by `Cluster::reader`. The reader borrows the cluster and keeps its own reference to
+/// the decompressed data, so handing out a blob is a plain slice operation that takes no lock.
+#[derive(Debug)]
+pub struct ClusterReader<'c> {
+    view: &'c [u8],
+    decompressed: Option<Arc<Vec<u8>>>,
+    blob_list: BlobList,
+}
+
+impl<'c> ClusterReader<'c> {
+    /// Returns the number of blobs in the cluster.
+    pub fn blob_count(&self) -> u32 {
+        self.blob_list.count() as u32
+    }
+
+    /// Returns the data of blob `idx`.
+    pub fn get(&self, idx: u32) -> Result<&[u8]> {
+        let range = self.blob_list.range(idx).ok_or(Error::OutOfBounds)?;
+        let data = match self.decompressed {
+            Some(ref data) => data.as_slice(),
+            None => &self.view[1..],
+        };
+
+        data.get(range).ok_or(Error::OutOfBounds)
+    }
+}
+
+impl<'a, 'b: 'a> From<&Blob<'a, 'b>> for Vec<u8> {
+    fn from(blob: &Blob<'a, 'b>) -> Vec<u8> {
+        blob.borrow_slice().to_vec()
+    }
+}
+
+/// Copies the data out, releasing the lock on the cluster.
+impl<'a, 'b: 'a> From<Blob<'a, 'b>> for Vec<u8> {
+    fn from(blob: Blob<'a, 'b>) -> Vec<u8> {
+        Vec::from(&blob)
+    }
+}
+
+/// Streams the data of a single blob, see `Cluster::blob_reader`.
+pub struct BlobReader<'a>(io::Take<Box<dyn Read + Send + 'a>>);
+
+impl<'a> Read for BlobReader<'a> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.0.read(buf)
+    }
+}
+
+impl<'a> fmt::Debug for BlobReader<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BlobReader")
+            .field("remaining", &self.0.limit())
+            .finish()
+    }
+}
+
+/// Decompressed cluster data that can be read through a `Cursor`.
+struct SharedData(Arc<Vec<u8>>);
+
+impl AsRef<[u8]> for SharedData {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
 #[self_referencing]
 pub struct Blob<'a, 'b: 'a> {
     guard: std::sync::RwLockReadGuard<'b, InnerCluster<'a>>,
@@ -143,6 +489,46 @@ pub struct Blob<'a, 'b: 'a> {
     slice: &'this [u8],
 }
 
+impl<'a, 'b: 'a> Blob<'a, 'b> {
+    /// Returns the blob as text, failing if it isn't valid UTF-8.
+    pub fn as_str(&self) -> Result<&str> {
+        Ok(std::str::from_utf8(self.borrow_slice())?)
+    }
+
+    /// Returns the blob as text, with invalid UTF-8 replaced by `U+FFFD`.
+    pub fn to_string_lossy(&self) -> String {
+        String::from_utf8_lossy(self.borrow_slice()).into_owned()
+    }
+
+    /// Returns `true` if the blob has no data.
+    pub fn is_empty(&self) -> bool {
+        self.borrow_slice().is_empty()
+    }
+
+    /// Guesses whether the blob is text, by checking that its first kilobyte is valid UTF-8
+    /// without any NUL bytes. Empty blobs count as text.
+    pub fn is_text(&self) -> bool {
+        let data = self.borrow_slice();
+        let head = &data[..data.len().min(1024)];
+        if head.contains(&0) {
+            return false;
+        }
+
+        match std::str::from_utf8(head) {
+            Ok(_) => true,
+            // a character cut off at the end of the checked range is fine
+            Err(err) => err.error_len().is_none() && head.len() < data.len(),
+        }
+    }
+
+    /// Returns the bytes in `range` of the blob, e.g. the start of it to sniff its type.
+    ///
+    /// Returns `Error::OutOfBounds` if the range doesn't lie within the blob.
+    pub fn slice(&self, range: Range<usize>) -> Result<&[u8]> {
+        self.borrow_slice().get(range).ok_or(Error::OutOfBounds)
+    }
+}
+
 impl<'a, 'b: 'a> Deref for Blob<'a, 'b> {
     type Target = [u8];
     fn deref(&self) -> &Self::Target {
@@ -158,28 +544,31 @@ impl<'a, 'b: 'a> AsRef<[u8]> for Blob<'a, 'b> {
 
 impl<'a> InnerCluster<'a> {
     fn new(
-        master_view: &'a Mmap,
-        cluster_list: &'a Vec<u64>,
+        master_view: &'a [u8],
+        cluster_list: &'a [u64],
         idx: u32,
         checksum_pos: u64,
         version: u16,
+        cache: Option<&'a ClusterCache>,
     ) -> Result<Self> {
+        let cluster_idx = idx;
         let idx = idx as usize;
-        let start = cluster_list[idx];
-        let end = if idx < cluster_list.len() - 1 {
-            cluster_list[idx + 1]
-        } else {
-            checksum_pos
+        let start = *cluster_list.get(idx).ok_or(Error::OutOfBounds)?;
+        let end = match cluster_list.get(idx + 1) {
+            Some(end) => *end,
+            None => checksum_pos,
         };
 
-        assert!(end > start);
+        if end <= start {
+            return Err(Error::OutOfBounds);
+        }
         let cluster_size = end - start;
         let cluster_view = master_view
             .get(start as usize..end as usize)
             .ok_or(Error::OutOfBounds)?;
 
         let (extended, compression) =
-            parse_details(cluster_view.first().ok_or(Error::OutOfBounds)?)?;
+            parse_cluster_info(*cluster_view.first().ok_or(Error::OutOfBounds)?)?;
 
         // extended clusters are only allowed in version 6
         if extended && version != 6 {
@@ -187,8 +576,12 @@ impl<'a> InnerCluster<'a> {
         }
 
         let blob_list = if Compression::None == compression {
-            let cur = Cursor::new(&cluster_view[1..]);
-            Some(parse_blob_list(cur, extended)?)
+            // the blob list starts with at least one offset
+            let offset_size = if extended { 8 } else { 4 };
+            if cluster_view.len() < 1 + offset_size {
+                return Err(Error::InvalidHeader);
+            }
+            Some(parse_blob_list(&cluster_view[1..], extended)?)
         } else {
             None
         };
@@ -202,6 +595,8 @@ impl<'a> InnerCluster<'a> {
             view: cluster_view,
             decompressed: None,
             blob_list,
+            idx: cluster_idx,
+            cache,
         })
     }
 
@@ -215,33 +610,27 @@ impl<'a> InnerCluster<'a> {
     }
 
     fn decompress(&mut self) -> Result<()> {
-        if self.decompressed.is_none() {
-            match self.compression {
-                Compression::Lzma2 => {
-                    let mut decoder = XzDecoder::new(&self.view[1..]);
-                    let mut d = Vec::with_capacity(self.view.len());
-                    decoder.read_to_end(&mut d)?;
-                    self.decompressed = Some(d);
-                }
-                Compression::Bzip2 => {
-                    todo!("bzip2");
-                }
-                Compression::Zlib => {
-                    todo!("zlib");
-                }
-                Compression::Zstd => {
-                    let out = zstd::stream::decode_all(&self.view[1..])?;
-                    self.decompressed = Some(out);
-                }
-                Compression::None => {}
+        if self.decompressed.is_none() && self.compression != Compression::None {
+            self.decompressed = self.cache.and_then(|cache| cache.get(self.idx));
+        }
+
+        if self.decompressed.is_none() && self.compression != Compression::None {
+            let capacity = self.view.len() * self.compression.typical_expansion() as usize;
+            let mut d = Vec::with_capacity(capacity);
+            self.decode_into(&mut d)?;
+            self.decompressed = Some(Arc::new(d));
+
+            if let (Some(cache), Some(data)) = (self.cache, &self.decompressed) {
+                cache.record_decompression(data.len());
+                cache.insert(self.idx, data.clone());
             }
         }
 
         if self.blob_list.is_none() {
             match self.compression {
                 Compression::Lzma2 | Compression::Bzip2 | Compression::Zlib | Compression::Zstd => {
-                    let cur = Cursor::new(self.decompressed.as_ref().unwrap());
-                    let blob_list = parse_blob_list(cur, self.extended)?;
+                    let data = self.decompressed.as_ref().unwrap().as_slice();
+                    let blob_list = parse_blob_list(data, self.extended)?;
                     self.blob_list = Some(blob_list);
                 }
                 Compression::None => {}
@@ -251,44 +640,126 @@ impl<'a> InnerCluster<'a> {
         Ok(())
     }
 
+    fn decompress_partial(&mut self) -> Result<usize> {
+        if self.decompress().is_err() {
+            let mut data = Vec::new();
+            // everything decoded before the error is kept in `data`
+            let _ = match self.compression {
+                Compression::Lzma2 => XzDecoder::new(&self.view[1..]).read_to_end(&mut data),
+                Compression::Zstd => zstd::stream::read::Decoder::with_buffer(&self.view[1..])
+                    .and_then(|mut decoder| decoder.read_to_end(&mut data)),
+                compression => return Err(Error::UnsupportedCompression(compression)),
+            };
+            self.blob_list = Some(parse_blob_list(&data, self.extended)?);
+            self.decompressed = Some(Arc::new(data));
+        }
+
+        let list = self.blob_list.as_ref().ok_or(Error::MissingBlobList)?;
+        let len = match self.decompressed {
+            Some(ref data) => data.len(),
+            None => self.view.len() - 1,
+        };
+
+        Ok((0..list.count() as u32)
+            .take_while(|idx| list.range(*idx).is_some_and(|range| range.end <= len))
+            .count())
+    }
+
+    /// Decodes the cluster data into `out`, replacing its contents.
+    fn decode_into(&self, out: &mut Vec<u8>) -> Result<()> {
+        out.clear();
+        match self.compression {
+            Compression::Lzma2 => {
+                let mut decoder = XzDecoder::new(&self.view[1..]);
+                decoder.read_to_end(out)?;
+            }
+            Compression::Bzip2 | Compression::Zlib => {
+                return Err(Error::UnsupportedCompression(self.compression));
+            }
+            Compression::Zstd => {
+                zstd::stream::copy_decode(&self.view[1..], &mut *out)?;
+            }
+            Compression::None => out.extend_from_slice(&self.view[1..]),
+        }
+
+        Ok(())
+    }
+
     fn get_blob(&self, idx: u32) -> Result<&[u8]> {
         match self.blob_list {
             Some(ref list) => {
-                let start = list[idx as usize] as usize;
-                let n = idx as usize + 1;
-                let end = if list.len() > n {
-                    list[n] as usize
-                } else {
-                    self.size as usize
-                };
-
-                Ok(match self.compression {
+                let range = list.range(idx).ok_or(Error::OutOfBounds)?;
+
+                match self.compression {
                     Compression::Lzma2
                     | Compression::Bzip2
                     | Compression::Zlib
                     | Compression::Zstd => {
                         // decompressed, so we know this exists
-                        &self.decompressed.as_ref().unwrap().as_slice()[start..end]
+                        self.decompressed.as_ref().unwrap().get(range)
                     }
-                    Compression::None => &self.view[1 + start..1 + end],
-                })
+                    Compression::None => self.view.get(1 + range.start..1 + range.end),
+                }
+                .ok_or(Error::OutOfBounds)
             }
             None => Err(Error::MissingBlobList),
         }
     }
 }
 
-/// Parses the cluster information.
+/// Reads the blob offset table from the start of a stream of cluster data.
+///
+/// Returns the stream, positioned after the part of the table that was read, and the range of
+/// blob `idx` relative to that position.
+fn stream_blob_range<'a, R: Read + Send + 'a>(
+    mut reader: R,
+    idx: u32,
+    extended: bool,
+) -> Result<(Box<dyn Read + Send + 'a>, Range<usize>)> {
+    let offset_size = if extended { 8 } else { 4 };
+    let read_offset = |reader: &mut R| -> Result<u64> {
+        Ok(if extended {
+            reader.read_u64::<LittleEndian>()?
+        } else {
+            reader.read_u32::<LittleEndian>()? as u64
+        })
+    };
+
+    let first = read_offset(&mut reader)?;
+    if idx as u64 + 1 >= first / offset_size {
+        return Err(Error::OutOfBounds);
+    }
+    let mut offsets = vec![first];
+    for _ in 0..=idx {
+        offsets.push(read_offset(&mut reader)?);
+    }
+
+    let consumed = offsets.len() as u64 * offset_size;
+    let list = BlobList::new(offsets);
+    let range = list.range(idx).ok_or(Error::OutOfBounds)?;
+    // the blobs start after the whole table
+    if (range.start as u64) < first {
+        return Err(Error::InvalidHeader);
+    }
+    let start = range.start - consumed as usize;
+
+    Ok((Box::new(reader), start..start + range.len()))
+}
+
+/// Parses a cluster info byte into whether the cluster is extended and its compression.
+///
+/// The three high bits are reserved and ignored.
 ///
 /// Fourth low bits:
 ///   - 0: default (no compression),
 ///   - 1: none (inherited from Zeno),
 ///   - 4: LZMA2 compressed
+///
 /// Firth bits :
 ///   - 0: normal (OFFSET_SIZE=4)
 ///   - 1: extended (OFFSET_SIZE=8)
-fn parse_details(details: &u8) -> Result<(bool, Compression)> {
-    let slice = &[*details];
+pub fn parse_cluster_info(info: u8) -> Result<(bool, Compression)> {
+    let slice = &[info];
     let mut reader = BitReader::new(slice);
     // skip first three bits
     reader.skip(3)?;
@@ -299,7 +770,9 @@ fn parse_details(details: &u8) -> Result<(bool, Compression)> {
     Ok((reader.read_bool()?, Compression::from(reader.read_u8(4)?)?))
 }
 
-fn parse_blob_list<T: ReadBytesExt>(mut cur: T, extended: bool) -> Result<Vec<u64>> {
+/// Parses the blob offset table at the start of the cluster data `data`.
+fn parse_blob_list(data: &[u8], extended: bool) -> Result<BlobList> {
+    let mut cur = Cursor::new(data);
     let mut blob_list = Vec::new();
 
     // determine the count of blobs, by reading the first offset
@@ -309,11 +782,16 @@ fn parse_blob_list<T: ReadBytesExt>(mut cur: T, extended: bool) -> Result<Vec<u6
         cur.read_u32::<LittleEndian>()? as u64
     };
 
+    // the table itself is part of the data, so a larger first offset means the cluster is
+    // corrupt; checking this keeps a bogus count from reading a huge table
+    if first > data.len() as u64 {
+        return Err(Error::InvalidHeader);
+    }
     let count = if extended { first / 8 } else { first / 4 };
 
     blob_list.push(first);
 
-    for _ in 0..(count as usize - 1) {
+    for _ in 1..count {
         if extended {
             blob_list.push(cur.read_u64::<LittleEndian>()?);
         } else {
@@ -321,5 +799,500 @@ fn parse_blob_list<T: ReadBytesExt>(mut cur: T, extended: bool) -> Result<Vec<u6
         }
     }
 
-    Ok(blob_list)
+    Ok(BlobList::new(blob_list))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::ArchiveBuilder;
+
+    #[test]
+    fn test_compression_from_str() {
+        for compression in [
+            Compression::None,
+            Compression::Zlib,
+            Compression::Bzip2,
+            Compression::Lzma2,
+            Compression::Zstd,
+        ] {
+            assert_eq!(
+                compression.to_string().parse::<Compression>().unwrap(),
+                compression
+            );
+        }
+        assert_eq!("XZ".parse::<Compression>().unwrap(), Compression::Lzma2);
+        assert_eq!("Zstd".parse::<Compression>().unwrap(), Compression::Zstd);
+        assert!(matches!(
+            "gzip".parse::<Compression>(),
+            Err(Error::UnknownCompressionName(name)) if name == "gzip"
+        ));
+    }
+
+    #[test]
+    fn test_typical_expansion() {
+        assert_eq!(Compression::None.typical_expansion(), 1);
+        assert_eq!(Compression::Zstd.typical_expansion(), 3);
+        assert_eq!(Compression::Lzma2.typical_expansion(), 4);
+        for compression in [Compression::Zlib, Compression::Bzip2] {
+            assert!((2..=8).contains(&compression.typical_expansion()));
+        }
+    }
+
+    #[test]
+    fn test_decompress_into() {
+        let mut b = ArchiveBuilder::new(5);
+        let blobs = vec![vec![1u8; 100], vec![2u8; 50]];
+        b.cluster(Compression::Lzma2, blobs.clone());
+        b.cluster(Compression::Zstd, blobs.clone());
+        b.cluster(Compression::None, blobs);
+        let zim = b.open("decompress_into");
+
+        let mut buf = Vec::with_capacity(4096);
+        let ptr = buf.as_ptr();
+        for idx in 0..zim.header.cluster_count {
+            let cluster = zim.get_cluster(idx).unwrap();
+            cluster.decompress_into(&mut buf).unwrap();
+            assert_eq!(buf.len() as u64, cluster.decompressed_size().unwrap());
+            assert_eq!(&buf[12..112], &[1u8; 100][..]);
+            // the buffer was large enough, so it was never reallocated
+            assert_eq!(buf.as_ptr(), ptr);
+        }
+    }
+
+    #[test]
+    fn test_unsupported_compression() {
+        let mut b = ArchiveBuilder::new(5);
+        b.cluster(Compression::Bzip2, vec![b"data".to_vec()]);
+        b.cluster(Compression::Zlib, vec![b"data".to_vec()]);
+        let zim = b.open("unsupported_compression");
+
+        for idx in 0..zim.header.cluster_count {
+            let cluster = zim.get_cluster(idx).unwrap();
+            assert!(!cluster.compression().is_supported());
+            assert!(matches!(
+                cluster.get_blob(0),
+                Err(Error::UnsupportedCompression(c)) if c == cluster.compression()
+            ));
+        }
+        assert!(Compression::Zstd.is_supported());
+    }
+
+    #[test]
+    fn test_reader() {
+        let blobs = vec![b"first".to_vec(), vec![], b"third".to_vec()];
+        let mut b = ArchiveBuilder::new(5);
+        b.cluster(Compression::Lzma2, blobs.clone());
+        b.cluster(Compression::None, blobs.clone());
+        let zim = b.open("reader");
+
+        for idx in 0..zim.header.cluster_count {
+            let cluster = zim.get_cluster(idx).unwrap();
+            let reader = cluster.reader().unwrap();
+            assert_eq!(reader.blob_count(), 3);
+            let read: Vec<_> = (0..reader.blob_count())
+                .map(|i| reader.get(i).unwrap().to_vec())
+                .collect();
+            assert_eq!(read, blobs);
+            assert!(matches!(reader.get(3), Err(Error::OutOfBounds)));
+            // the cluster stays usable while the reader exists
+            assert_eq!(&*cluster.get_blob(0).unwrap(), b"first");
+        }
+    }
+
+    #[test]
+    fn test_blob_file_range() {
+        let blobs = vec![b"first".to_vec(), vec![], b"third".to_vec()];
+        let mut b = ArchiveBuilder::new(5);
+        b.cluster(Compression::Zstd, blobs.clone());
+        b.cluster(Compression::None, blobs);
+        let zim = b.open("blob_file_range");
+
+        let compressed = zim.get_cluster(0).unwrap();
+        assert_eq!(compressed.blob_file_range(0).unwrap(), None);
+
+        let cluster = zim.get_cluster(1).unwrap();
+        for idx in 0..3 {
+            let range = cluster.blob_file_range(idx).unwrap().unwrap();
+            let bytes = &zim.master_view[range.start as usize..range.end as usize];
+            assert_eq!(bytes, &*cluster.get_blob(idx).unwrap());
+        }
+        assert!(matches!(
+            cluster.blob_file_range(3),
+            Err(Error::OutOfBounds)
+        ));
+    }
+
+    #[test]
+    fn test_info_byte() {
+        let mut b = ArchiveBuilder::new(6);
+        b.cluster(Compression::Zstd, vec![b"a".to_vec()]);
+        b.extended_cluster(Compression::None, vec![b"b".to_vec()]);
+        let zim = b.open("info_byte");
+
+        for idx in 0..2 {
+            let cluster = zim.get_cluster(idx).unwrap();
+            let info = cluster.info_byte();
+            assert_eq!(
+                info,
+                zim.master_view[zim.cluster_list[idx as usize] as usize]
+            );
+            assert_eq!(
+                parse_cluster_info(info).unwrap(),
+                (cluster.is_extended(), cluster.compression())
+            );
+        }
+        assert!(zim.get_cluster(1).unwrap().is_extended());
+
+        // the reserved high bits are kept
+        let mut data = crate::test_util::raw_cluster(Compression::None, false, &[b"c".to_vec()]);
+        data[0] |= 0x20;
+        let cluster = Cluster::new(&data, &[0], 0, data.len() as u64, 5).unwrap();
+        assert_eq!(cluster.info_byte() & 0xe0, 0x20);
+        assert_eq!(cluster.compression(), Compression::None);
+    }
+
+    #[test]
+    fn test_truncated_cluster() {
+        // a cluster consisting of only the info byte
+        let view = [0xff, 0xff, 0xff, 0xff, 0, 0xff];
+        assert!(matches!(
+            Cluster::new(&view, &[4], 0, 5, 5),
+            Err(Error::InvalidHeader)
+        ));
+
+        // a first offset far beyond the end of the cluster
+        let view = [0, 0xff, 0xff, 0xff, 0xff, 0, 0, 0, 0];
+        assert!(matches!(
+            Cluster::new(&view, &[0], 0, 9, 5),
+            Err(Error::InvalidHeader)
+        ));
+        let compressed = zstd::encode_all(&[0xff, 0xff, 0xff, 0xff, 0, 0, 0, 0][..], 3).unwrap();
+        let mut view = vec![5];
+        view.extend_from_slice(&compressed);
+        let cluster = Cluster::new(&view, &[0], 0, view.len() as u64, 5).unwrap();
+        assert!(matches!(cluster.get_blob(0), Err(Error::InvalidHeader)));
+
+        // an extended cluster with room for a 4 byte offset only
+        let view = [0x10, 12, 0, 0, 0, 0xff];
+        assert!(matches!(
+            Cluster::new(&view, &[0], 0, 5, 6),
+            Err(Error::InvalidHeader)
+        ));
+    }
+
+    #[test]
+    fn test_blob_as_str() {
+        let mut b = ArchiveBuilder::new(5);
+        b.cluster(
+            Compression::Zstd,
+            vec!["<p>Zürich</p>".into(), vec![0x89, b'P', 0xff]],
+        );
+        let zim = b.open("blob_as_str");
+        let cluster = zim.get_cluster(0).unwrap();
+
+        let text = cluster.get_blob(0).unwrap();
+        assert_eq!(text.as_str().unwrap(), "<p>Zürich</p>");
+        assert_eq!(text.to_string_lossy(), "<p>Zürich</p>");
+
+        let binary = cluster.get_blob(1).unwrap();
+        assert!(matches!(binary.as_str(), Err(Error::Parsing(_))));
+        assert_eq!(binary.to_string_lossy(), "\u{fffd}P\u{fffd}");
+    }
+
+    #[test]
+    fn test_blob_slice() {
+        let mut b = ArchiveBuilder::new(5);
+        b.cluster(Compression::Zstd, vec![b"<html>body</html>".to_vec()]);
+        let zim = b.open("blob_slice");
+        let cluster = zim.get_cluster(0).unwrap();
+        let blob = cluster.get_blob(0).unwrap();
+
+        assert_eq!(blob.slice(0..6).unwrap(), b"<html>");
+        assert_eq!(blob.slice(17..17).unwrap(), b"");
+        assert!(matches!(blob.slice(10..18), Err(Error::OutOfBounds)));
+    }
+
+    #[test]
+    fn test_try_get_blob() {
+        let mut b = ArchiveBuilder::new(5);
+        b.cluster(Compression::Lzma2, vec![b"a".to_vec(), b"b".to_vec()]);
+        b.cluster(Compression::None, vec![b"c".to_vec()]);
+        let zim = b.open("try_get_blob");
+
+        let compressed = zim.get_cluster(0).unwrap();
+        assert_eq!(compressed.blob_count().unwrap(), 2);
+        assert_eq!(compressed.try_get_blob(1).unwrap().unwrap().as_ref(), b"b");
+        assert!(compressed.try_get_blob(2).unwrap().is_none());
+        assert!(matches!(compressed.get_blob(2), Err(Error::OutOfBounds)));
+
+        let uncompressed = zim.get_cluster(1).unwrap();
+        assert_eq!(uncompressed.blob_count().unwrap(), 1);
+        assert_eq!(
+            uncompressed.try_get_blob(0).unwrap().unwrap().as_ref(),
+            b"c"
+        );
+        assert!(uncompressed.try_get_blob(1).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_decompress_partial() {
+        use rand::rngs::StdRng;
+        use rand::{Rng, SeedableRng};
+
+        let mut rng = StdRng::seed_from_u64(1);
+        let noise: Vec<u8> = (0..512 * 1024).map(|_| rng.gen()).collect();
+        let blobs = vec![b"first".to_vec(), b"second".to_vec(), noise];
+
+        for compression in [Compression::Zstd, Compression::Lzma2] {
+            let mut data = crate::test_util::raw_cluster(compression, false, &blobs);
+            let cluster = Cluster::new(&data, &[0], 0, data.len() as u64, 5).unwrap();
+            assert_eq!(cluster.decompress_partial().unwrap(), 3);
+
+            data.truncate(data.len() / 2);
+            let cluster = Cluster::new(&data, &[0], 0, data.len() as u64, 5).unwrap();
+            assert!(cluster.decompress().is_err());
+            assert_eq!(cluster.decompress_partial().unwrap(), 2);
+            assert_eq!(cluster.get_blob(0).unwrap().as_ref(), b"first");
+            assert_eq!(cluster.get_blob(1).unwrap().as_ref(), b"second");
+            assert!(matches!(cluster.get_blob(2), Err(Error::OutOfBounds)));
+        }
+    }
+
+    #[test]
+    fn test_uncompressed_is_zero_copy() {
+        let mut b = ArchiveBuilder::new(5);
+        b.cluster(
+            Compression::None,
+            vec![b"first".to_vec(), b"second".to_vec()],
+        );
+        let zim = b.open("uncompressed_is_zero_copy");
+        let cluster = zim.get_cluster(0).unwrap();
+
+        // the blob list is parsed up front, so sizes are known without decompressing
+        assert!(!cluster.read().unwrap().needs_decompression());
+        assert_eq!(cluster.get_blob_size(1), Some(6));
+        assert_eq!(cluster.blob_count().unwrap(), 2);
+
+        let blob = cluster.get_blob(1).unwrap();
+        assert_eq!(blob.as_ref(), b"second");
+        let range = cluster.blob_file_range(1).unwrap().unwrap();
+        assert_eq!(
+            blob.as_ptr(),
+            zim.master_view[range.start as usize..].as_ptr()
+        );
+        drop(blob);
+
+        cluster.decompress().unwrap();
+        assert!(cluster.read().unwrap().decompressed.is_none());
+        assert_eq!(zim.stats().clusters_decompressed, 0);
+    }
+
+    #[test]
+    fn test_blob_is_text() {
+        // the first kilobyte ends in the middle of a character
+        let long = format!("a{}", "ü".repeat(1000)).into_bytes();
+        let mut b = ArchiveBuilder::new(5);
+        b.cluster(
+            Compression::Zstd,
+            vec![
+                vec![],
+                b"<p>text</p>".to_vec(),
+                long,
+                vec![0x89, b'P', b'N', b'G', 0],
+                vec![b'a', 0xff],
+            ],
+        );
+        let zim = b.open("blob_is_text");
+        let cluster = zim.get_cluster(0).unwrap();
+
+        let empty = cluster.get_blob(0).unwrap();
+        assert!(empty.is_empty());
+        assert!(empty.is_text());
+        drop(empty);
+
+        let results: Vec<_> = (1..5)
+            .map(|idx| {
+                let blob = cluster.get_blob(idx).unwrap();
+                (blob.is_empty(), blob.is_text())
+            })
+            .collect();
+        assert_eq!(
+            results,
+            vec![(false, true), (false, true), (false, false), (false, false)]
+        );
+    }
+
+    #[test]
+    fn test_blob_offsets() {
+        let blobs = vec![b"one".to_vec(), b"three".to_vec(), b"x".to_vec()];
+        let mut b = ArchiveBuilder::new(5);
+        b.cluster(Compression::Lzma2, blobs.clone());
+        b.cluster(Compression::None, blobs);
+        let zim = b.open("blob_offsets");
+
+        for idx in 0..2 {
+            let cluster = zim.get_cluster(idx).unwrap();
+            let offsets = cluster.blob_offsets().unwrap();
+            assert_eq!(offsets, vec![16, 19, 24, 25]);
+            assert_eq!(offsets.len(), cluster.blob_count().unwrap() as usize + 1);
+            assert!(offsets.windows(2).all(|pair| pair[0] < pair[1]));
+        }
+    }
+
+    #[test]
+    fn test_invalid_cluster_list() {
+        let view = [0, 8, 0, 0, 0, 8, 0, 0, 0];
+        assert!(matches!(
+            Cluster::new(&view, &[], 0, 9, 5),
+            Err(Error::OutOfBounds)
+        ));
+        assert!(matches!(
+            Cluster::new(&view, &[0], 1, 9, 5),
+            Err(Error::OutOfBounds)
+        ));
+        // clusters out of order
+        assert!(matches!(
+            Cluster::new(&view, &[4, 2], 0, 9, 5),
+            Err(Error::OutOfBounds)
+        ));
+        assert!(matches!(
+            Cluster::new(&view, &[0], 0, 20, 5),
+            Err(Error::OutOfBounds)
+        ));
+    }
+
+    #[test]
+    fn test_random_clusters_never_panic() {
+        use rand::rngs::StdRng;
+        use rand::{Rng, SeedableRng};
+
+        let mut rng = StdRng::seed_from_u64(3);
+        for _ in 0..5000 {
+            let len = rng.gen_range(0..64);
+            let mut view: Vec<u8> = (0..len).map(|_| rng.gen()).collect();
+            // mostly use valid info bytes, to get past the compression check
+            if let Some(info) = view.first_mut() {
+                *info = [0, 1, 4, 5, 0x10, 0x11][rng.gen_range(0..6)];
+            }
+            let version = rng.gen_range(5..7);
+            if let Ok(cluster) = Cluster::new(&view, &[0], 0, view.len() as u64, version) {
+                for idx in 0..4 {
+                    let _ = cluster.get_blob(idx).map(|blob| blob.len());
+                    let _ = cluster.blob_file_range(idx);
+                }
+                let _ = cluster
+                    .reader()
+                    .map(|reader| reader.get(0).map(|b| b.len()));
+                let _ = cluster.decompressed_size();
+            }
+        }
+    }
+
+    #[test]
+    fn test_blob_reader() {
+        let blobs = vec![
+            b"first".to_vec(),
+            vec![],
+            vec![7; 100_000],
+            b"last".to_vec(),
+        ];
+        let mut b = ArchiveBuilder::new(6);
+        b.cluster(Compression::Lzma2, blobs.clone());
+        b.cluster(Compression::Zstd, blobs.clone());
+        b.extended_cluster(Compression::Zstd, blobs.clone());
+        b.cluster(Compression::None, blobs.clone());
+        let zim = b.open("blob_reader");
+
+        for cluster in 0..zim.header.cluster_count {
+            // streamed straight from the compressed data
+            for (idx, blob) in blobs.iter().enumerate() {
+                let mut reader = zim
+                    .get_cluster(cluster)
+                    .unwrap()
+                    .blob_reader(idx as u32)
+                    .unwrap();
+                let mut data = Vec::new();
+                reader.read_to_end(&mut data).unwrap();
+                assert_eq!(&data, blob);
+            }
+            assert!(matches!(
+                zim.get_cluster(cluster).unwrap().blob_reader(4),
+                Err(Error::OutOfBounds)
+            ));
+
+            // read from the decompressed data
+            let handle = zim.get_cluster(cluster).unwrap();
+            handle.decompress().unwrap();
+            let mut data = Vec::new();
+            handle
+                .blob_reader(3)
+                .unwrap()
+                .read_to_end(&mut data)
+                .unwrap();
+            assert_eq!(data, b"last");
+        }
+    }
+
+    #[test]
+    fn test_blob_into_vec() {
+        let mut b = ArchiveBuilder::new(5);
+        b.cluster(
+            Compression::Zstd,
+            vec![b"first".to_vec(), b"second".to_vec()],
+        );
+        let zim = b.open("blob_into_vec");
+        let cluster = zim.get_cluster(0).unwrap();
+
+        let blob = cluster.get_blob(0).unwrap();
+        let borrowed: Vec<u8> = (&blob).into();
+        assert_eq!(borrowed, b"first");
+        let owned: Vec<u8> = blob.into();
+        assert_eq!(owned, b"first");
+
+        // the guard was dropped, so the cluster can be locked for writing again
+        cluster.decompress().unwrap();
+        let second: Vec<u8> = cluster.get_blob(1).unwrap().into();
+        assert_eq!(second, b"second");
+    }
+
+    #[test]
+    fn test_lock_poisoned() {
+        let mut b = ArchiveBuilder::new(5);
+        b.cluster(Compression::Zstd, vec![b"data".to_vec()]);
+        let zim = b.open("lock_poisoned");
+        let cluster = zim.get_cluster(0).unwrap();
+
+        std::thread::scope(|s| {
+            let poisoner = s.spawn(|| {
+                let _guard = cluster.0.write().unwrap();
+                panic!("poison the lock");
+            });
+            assert!(poisoner.join().is_err());
+        });
+
+        assert!(matches!(cluster.get_blob(0), Err(Error::LockPoisoned)));
+        assert!(matches!(cluster.decompress(), Err(Error::LockPoisoned)));
+        assert_eq!(cluster.compression(), Compression::Zstd);
+        assert_eq!(cluster.get_blob_size(0), None);
+        // other handles of the same cluster are unaffected
+        assert_eq!(&*zim.get_cluster(0).unwrap().get_blob(0).unwrap(), b"data");
+    }
+
+    #[test]
+    fn test_decompressed() {
+        let blobs = vec![b"first".to_vec(), b"second".to_vec(), vec![]];
+        let mut b = ArchiveBuilder::new(5);
+        b.cluster(Compression::Zstd, blobs.clone());
+        b.cluster(Compression::None, blobs.clone());
+        let zim = b.open("decompressed");
+
+        let blob_len: usize = blobs.iter().map(|b| b.len()).sum();
+        for idx in 0..zim.header.cluster_count {
+            let data = zim.get_cluster(idx).unwrap().decompressed().unwrap();
+            // four offsets followed by the blobs
+            assert_eq!(data.len(), 4 * 4 + blob_len);
+            assert_eq!(&data[16..], b"firstsecond");
+        }
+    }
 }
@@ -1,8 +1,11 @@
+use std::borrow::Cow;
 use std::convert::TryFrom;
 use std::io::BufRead;
 use std::io::Cursor;
+use std::io::Read;
 
 use byteorder::{LittleEndian, ReadBytesExt};
+use percent_encoding::{utf8_percent_encode, AsciiSet, CONTROLS};
 
 use crate::errors::{Error, Result};
 use crate::mime_type::MimeType;
@@ -10,6 +13,20 @@ use crate::namespace::Namespace;
 use crate::target::Target;
 use crate::zim::Zim;
 
+/// The bytes escaped in a URL path segment, the same set the `url` crate uses.
+const PATH_SEGMENT: &AsciiSet = &CONTROLS
+    .add(b' ')
+    .add(b'"')
+    .add(b'#')
+    .add(b'<')
+    .add(b'>')
+    .add(b'`')
+    .add(b'?')
+    .add(b'{')
+    .add(b'}')
+    .add(b'/')
+    .add(b'%');
+
 /// Holds metadata about an article
 #[derive(Debug)]
 pub struct DirectoryEntry {
@@ -26,14 +43,39 @@ pub struct DirectoryEntry {
     /// the URL is used as title
     pub title: String,
     pub target: Option<Target>,
+    /// the MIME type number as stored in the entry
+    mime_index: u16,
+    /// extra parameters, stored after the title; unused by current writers
+    parameters: Vec<u8>,
+    /// the original URL and title bytes, only kept if they aren't valid UTF-8
+    raw_url: Option<Vec<u8>>,
+    raw_title: Option<Vec<u8>>,
 }
 
 impl DirectoryEntry {
+    /// Parses a directory entry, failing if the URL or title aren't valid UTF-8.
     pub fn new(zim: &Zim, s: &[u8]) -> Result<DirectoryEntry> {
+        DirectoryEntry::parse(zim, s, false)
+    }
+
+    /// Parses a directory entry, replacing invalid UTF-8 in the URL and title.
+    ///
+    /// The original bytes are still available through `url_bytes` and `title_bytes`.
+    pub fn new_lenient(zim: &Zim, s: &[u8]) -> Result<DirectoryEntry> {
+        DirectoryEntry::parse(zim, s, true)
+    }
+
+    fn parse(zim: &Zim, s: &[u8], lenient: bool) -> Result<DirectoryEntry> {
+        // major versions 5 and 6 share the same entry layout, other versions may not
+        match zim.header.version_major {
+            5 | 6 => {}
+            version => return Err(Error::InvalidVersion(version)),
+        }
+
         let mut cur = Cursor::new(s);
         let mime_id = cur.read_u16::<LittleEndian>()?;
-        let mime_type = zim.get_mimetype(mime_id).ok_or(Error::UnknownMimeType)?;
-        let _ = cur.read_u8()?;
+        let mime_type = MimeType::from_raw(mime_id, &zim.mime_table)?;
+        let parameter_len = cur.read_u8()?;
         let namespace = cur.read_u8()?;
         let rev = cur.read_u32::<LittleEndian>().ok();
 
@@ -48,18 +90,10 @@ impl DirectoryEntry {
             Some(Target::Cluster(cluster_number, blob_number))
         };
 
-        let url = {
-            let mut vec = Vec::new();
-            let size = cur.read_until(0, &mut vec)?;
-            vec.truncate(size - 1);
-            String::from_utf8(vec)?
-        };
-        let title = {
-            let mut vec = Vec::new();
-            let size = cur.read_until(0, &mut vec)?;
-            vec.truncate(size - 1);
-            String::from_utf8(vec)?
-        };
+        let (url, raw_url) = read_string(&mut cur, lenient)?;
+        let (title, raw_title) = read_string(&mut cur, lenient)?;
+        let mut parameters = vec![0; parameter_len as usize];
+        cur.read_exact(&mut parameters)?;
 
         Ok(DirectoryEntry {
             mime_type,
@@ -68,6 +102,210 @@ impl DirectoryEntry {
             url,
             title,
             target,
+            mime_index: mime_id,
+            parameters,
+            raw_url,
+            raw_title,
         })
     }
+
+    /// Returns the URL as stored in the archive.
+    pub fn url_bytes(&self) -> &[u8] {
+        self.raw_url.as_deref().unwrap_or(self.url.as_bytes())
+    }
+
+    /// Returns the title as stored in the archive.
+    pub fn title_bytes(&self) -> &[u8] {
+        self.raw_title.as_deref().unwrap_or(self.title.as_bytes())
+    }
+
+    /// Returns the URL, with invalid UTF-8 replaced by `U+FFFD`.
+    pub fn url_lossy(&self) -> Cow<'_, str> {
+        String::from_utf8_lossy(self.url_bytes())
+    }
+
+    /// Returns `namespace/url` percent-encoded for use in an `<a href>`, e.g. `A/New%20York`.
+    ///
+    /// Every `/` separated segment of the URL is percent-encoded as a URL path segment: control
+    /// characters, non-ASCII bytes, spaces and ``"#%<>?`{}`` are escaped.
+    /// `Zim::get_by_encoded_url` accepts the encoded URL again.
+    pub fn href(&self) -> String {
+        let segments: Vec<_> = self
+            .url
+            .split('/')
+            .map(|segment| utf8_percent_encode(segment, PATH_SEGMENT).to_string())
+            .collect();
+
+        format!("{}/{}", self.namespace as u8 as char, segments.join("/"))
+    }
+
+    /// Returns the MIME type number as stored in the entry.
+    ///
+    /// This is an index into the MIME type list, or one of the special values `0xffff` for
+    /// redirects, `0xfffe` for link targets and `0xfffd` for deleted entries.
+    pub fn raw_mime_index(&self) -> u16 {
+        self.mime_index
+    }
+
+    /// Returns the extra parameter data of this entry, empty for most archives.
+    pub fn parameters(&self) -> &[u8] {
+        &self.parameters
+    }
+}
+
+/// Makes a single `/` separated segment of a URL safe to use as a file name.
+///
+/// URLs come from the archive and can't be trusted, so `.` and `..` are escaped, as is `\\`,
+/// which separates paths on windows.
+pub fn escape_path_segment(segment: &str) -> String {
+    match segment {
+        "." => "%2E".to_string(),
+        ".." => "%2E%2E".to_string(),
+        segment => segment.replace('\\', "%5C"),
+    }
+}
+
+/// Reads a null-terminated string.
+///
+/// In lenient mode invalid UTF-8 is replaced, and the original bytes are returned as well.
+fn read_string(cur: &mut Cursor<&[u8]>, lenient: bool) -> Result<(String, Option<Vec<u8>>)> {
+    let mut vec = Vec::new();
+    cur.read_until(0, &mut vec)?;
+    if vec.pop() != Some(0) {
+        return Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof).into());
+    }
+
+    match String::from_utf8(vec) {
+        Ok(s) => Ok((s, None)),
+        Err(err) if lenient => {
+            let raw = err.into_bytes();
+            Ok((String::from_utf8_lossy(&raw).into_owned(), Some(raw)))
+        }
+        Err(err) => Err(err.into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cluster::Compression;
+    use crate::test_util::{self, ArchiveBuilder};
+
+    use super::*;
+
+    #[test]
+    fn test_versions() {
+        for (major, minor) in [(5, 0), (6, 0), (6, 1)] {
+            let zim = test_util::sample(major).minor(minor).open("entry_versions");
+            assert_eq!(zim.header.version_major, major);
+
+            let entries: Vec<_> = zim
+                .iterate_by_urls()
+                .map(|e| (e.url, e.title, e.target))
+                .collect();
+            assert_eq!(
+                entries,
+                vec![
+                    (
+                        "Berlin".into(),
+                        "Berlin".into(),
+                        Some(Target::Cluster(0, 0))
+                    ),
+                    (
+                        "Main_Page".into(),
+                        "Main Page".into(),
+                        Some(Target::Cluster(0, 2))
+                    ),
+                    ("NYC".into(), "NYC".into(), Some(Target::Redirect(3))),
+                    (
+                        "New York".into(),
+                        "New York".into(),
+                        Some(Target::Cluster(0, 1))
+                    ),
+                    ("logo.png".into(), "".into(), Some(Target::Cluster(1, 0))),
+                    ("Language".into(), "".into(), Some(Target::Cluster(0, 4))),
+                    ("Title".into(), "".into(), Some(Target::Cluster(0, 3))),
+                ]
+            );
+        }
+
+        // an unknown layout is rejected instead of being misparsed
+        let mut zim = test_util::sample(6).open("entry_versions_unknown");
+        zim.header.version_major = 8;
+        assert!(matches!(
+            zim.get_by_url_index(0),
+            Err(Error::InvalidVersion(8))
+        ));
+    }
+
+    #[test]
+    fn test_raw_mime_index() {
+        let zim = test_util::sample(5).open("raw_mime_index");
+        let redirect = zim.get_by_url('A', "NYC").unwrap().unwrap();
+        assert_eq!(redirect.raw_mime_index(), 0xffff);
+
+        let article = zim.get_by_url('A', "Berlin").unwrap().unwrap();
+        let html = zim.mime_index("text/html").unwrap();
+        assert_eq!(article.raw_mime_index(), html);
+        assert_eq!(
+            zim.get_mimetype(article.raw_mime_index()),
+            Some(article.mime_type)
+        );
+    }
+
+    #[test]
+    fn test_href() {
+        let zim = test_util::sample(5).open("href");
+
+        let entry = zim.get_by_url('A', "New York").unwrap().unwrap();
+        assert_eq!(entry.href(), "A/New%20York");
+        let encoded = &entry.href()[2..];
+        assert_eq!(
+            zim.get_by_encoded_url('A', encoded).unwrap().unwrap().url,
+            "New York"
+        );
+
+        let mut entry = zim.get_by_url('A', "Berlin").unwrap().unwrap();
+        entry.url = "dir/Zürich?#100%".into();
+        assert_eq!(entry.href(), "A/dir/Z%C3%BCrich%3F%23100%25");
+    }
+
+    #[test]
+    fn test_parameters() {
+        let mut b = ArchiveBuilder::new(5);
+        let c = b.cluster(Compression::None, vec![b"a".to_vec(), b"b".to_vec()]);
+        b.article('A', "One", "First", "text/html", c, 0)
+            .parameters(&[1, 2, 3])
+            .article('A', "Two", "Second", "text/html", c, 1);
+        let zim = b.open("parameters");
+
+        let one = zim.get_by_url_index(0).unwrap();
+        assert_eq!(one.url, "One");
+        assert_eq!(one.title, "First");
+        assert_eq!(one.parameters(), &[1, 2, 3]);
+
+        let two = zim.get_by_url_index(1).unwrap();
+        assert_eq!(two.url, "Two");
+        assert!(two.parameters().is_empty());
+    }
+
+    #[test]
+    fn test_invalid_utf8() {
+        let mut b = ArchiveBuilder::new(5);
+        let c = b.cluster(Compression::None, vec![b"a".to_vec(), b"b".to_vec()]);
+        b.raw_article('A', b"Caf\xe9", b"Caf\xe9", "text/html", c, 0)
+            .article('A', "Tea", "Tea", "text/html", c, 1);
+        let zim = b.open("invalid_utf8");
+
+        assert!(zim.get_by_url_index(0).is_err());
+
+        let offset = zim.url_list[0] as usize;
+        let entry = DirectoryEntry::new_lenient(&zim, &zim.master_view[offset..]).unwrap();
+        assert_eq!(entry.url, "Caf\u{fffd}");
+        assert_eq!(entry.url_lossy(), "Caf\u{fffd}");
+        assert_eq!(entry.url_bytes(), b"Caf\xe9");
+        assert_eq!(entry.title_bytes(), b"Caf\xe9");
+
+        let urls: Vec<_> = zim.iterate_by_urls_lenient().map(|e| e.url).collect();
+        assert_eq!(urls, vec!["Caf\u{fffd}", "Tea"]);
+    }
 }
@@ -0,0 +1,172 @@
+use crate::errors::{Error, Result};
+use crate::namespace::Namespace;
+
+/// What a new entry points to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EntryPayload {
+    /// The content of the entry, stored in a cluster.
+    Content(Vec<u8>),
+    /// Another entry, given by namespace and URL.
+    Redirect(Namespace, String),
+}
+
+/// An entry to be written to an archive, as assembled by `DirectoryEntryBuilder`.
+///
+/// Unlike `DirectoryEntry`, which refers to data in an existing archive by index, this holds the
+/// content itself and refers to redirect targets by URL, as their index isn't known until all
+/// entries are sorted.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NewEntry {
+    pub namespace: Namespace,
+    pub url: String,
+    /// The title, empty if it is the same as the URL.
+    pub title: String,
+    /// The MIME type of the content, `None` for redirects.
+    pub mime_type: Option<String>,
+    pub payload: EntryPayload,
+}
+
+/// Assembles a `NewEntry`.
+///
+/// An entry has either content, which requires a MIME type, or a redirect target, never both.
+#[derive(Debug, Clone, Default)]
+pub struct DirectoryEntryBuilder {
+    namespace: Option<Namespace>,
+    url: Option<String>,
+    title: String,
+    mime_type: Option<String>,
+    content: Option<Vec<u8>>,
+    redirect: Option<(Namespace, String)>,
+}
+
+impl DirectoryEntryBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn namespace(mut self, namespace: Namespace) -> Self {
+        self.namespace = Some(namespace);
+        self
+    }
+
+    pub fn url(mut self, url: impl Into<String>) -> Self {
+        self.url = Some(url.into());
+        self
+    }
+
+    pub fn title(mut self, title: impl Into<String>) -> Self {
+        self.title = title.into();
+        self
+    }
+
+    pub fn mime_type(mut self, mime_type: impl Into<String>) -> Self {
+        self.mime_type = Some(mime_type.into());
+        self
+    }
+
+    /// Sets the content of the entry.
+    pub fn content(mut self, content: impl Into<Vec<u8>>) -> Self {
+        self.content = Some(content.into());
+        self
+    }
+
+    /// Makes the entry a redirect to the entry `url` in `namespace`.
+    pub fn redirect(mut self, namespace: Namespace, url: impl Into<String>) -> Self {
+        self.redirect = Some((namespace, url.into()));
+        self
+    }
+
+    /// Validates the entry and builds it.
+    pub fn build(self) -> Result<NewEntry> {
+        let invalid = |msg: &str| Error::InvalidEntry(msg.to_string());
+        let namespace = self.namespace.ok_or_else(|| invalid("missing namespace"))?;
+        let url = self.url.ok_or_else(|| invalid("missing url"))?;
+        if url.is_empty() {
+            return Err(invalid("empty url"));
+        }
+
+        let payload = match (self.content, self.redirect) {
+            (Some(_), Some(_)) => return Err(invalid("entry has both content and a redirect")),
+            (None, None) => return Err(invalid("entry has neither content nor a redirect")),
+            (Some(content), None) => {
+                if self.mime_type.is_none() {
+                    return Err(invalid("content requires a mime type"));
+                }
+                EntryPayload::Content(content)
+            }
+            (None, Some((ns, target))) => {
+                if self.mime_type.is_some() {
+                    return Err(invalid("redirects have no mime type"));
+                }
+                EntryPayload::Redirect(ns, target)
+            }
+        };
+
+        Ok(NewEntry {
+            namespace,
+            url,
+            title: self.title,
+            mime_type: self.mime_type,
+            payload,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build() {
+        let entry = DirectoryEntryBuilder::new()
+            .namespace(Namespace::Articles)
+            .url("Berlin")
+            .title("Berlin")
+            .mime_type("text/html")
+            .content(&b"<html>Berlin</html>"[..])
+            .build()
+            .unwrap();
+        assert_eq!(entry.url, "Berlin");
+        assert_eq!(entry.mime_type.as_deref(), Some("text/html"));
+        assert_eq!(
+            entry.payload,
+            EntryPayload::Content(b"<html>Berlin</html>".to_vec())
+        );
+
+        let redirect = DirectoryEntryBuilder::new()
+            .namespace(Namespace::Articles)
+            .url("Berlin_(city)")
+            .redirect(Namespace::Articles, "Berlin")
+            .build()
+            .unwrap();
+        assert_eq!(
+            redirect.payload,
+            EntryPayload::Redirect(Namespace::Articles, "Berlin".into())
+        );
+    }
+
+    #[test]
+    fn test_rejects_invalid() {
+        let base = DirectoryEntryBuilder::new()
+            .namespace(Namespace::Articles)
+            .url("Berlin");
+
+        let both = base
+            .clone()
+            .mime_type("text/html")
+            .content("content")
+            .redirect(Namespace::Articles, "Paris");
+        let invalid = vec![
+            both,
+            base.clone(),
+            base.clone().content("no mime type"),
+            base.clone()
+                .mime_type("text/html")
+                .redirect(Namespace::Articles, "Paris"),
+            DirectoryEntryBuilder::new().url("x").content("x"),
+        ];
+        for builder in invalid {
+            assert!(matches!(builder.build(), Err(Error::InvalidEntry(_))));
+        }
+    }
+}
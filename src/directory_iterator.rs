@@ -1,10 +1,12 @@
 use crate::directory_entry::DirectoryEntry;
+use crate::errors::Error;
 use crate::zim::Zim;
 
 pub struct DirectoryIterator<'a> {
     max: u32,
     next: u32,
     zim: &'a Zim,
+    lenient: bool,
 }
 
 impl<'a> DirectoryIterator<'a> {
@@ -13,6 +15,15 @@ impl<'a> DirectoryIterator<'a> {
             max: zim.header.article_count,
             next: 0,
             zim,
+            lenient: false,
+        }
+    }
+
+    /// Creates an iterator that replaces invalid UTF-8 in URLs and titles instead of stopping.
+    pub fn new_lenient(zim: &'a Zim) -> DirectoryIterator<'a> {
+        DirectoryIterator {
+            lenient: true,
+            ..DirectoryIterator::new(zim)
         }
     }
 }
@@ -21,17 +32,26 @@ impl<'a> std::iter::Iterator for DirectoryIterator<'a> {
     type Item = DirectoryEntry;
 
     fn next(&mut self) -> Option<Self::Item> {
-        if self.next >= self.max {
-            return None;
-        }
-
-        let dir_entry_ptr = self.zim.url_list[self.next as usize] as usize;
-        self.next += 1;
-        let slice = self.zim.master_view.get(dir_entry_ptr..);
+        while self.next < self.max {
+            let idx = self.next;
+            let dir_entry_ptr = self.zim.url_list[idx as usize] as usize;
+            self.next += 1;
+            let entry = match self.zim.master_view.get(dir_entry_ptr..) {
+                Some(slice) if self.lenient => DirectoryEntry::new_lenient(self.zim, slice),
+                Some(slice) => DirectoryEntry::new(self.zim, slice),
+                None => Err(Error::OutOfBounds),
+            };
 
-        match slice {
-            Some(slice) => DirectoryEntry::new(self.zim, slice).ok(),
-            None => None,
+            match entry {
+                Ok(entry) => return Some(entry),
+                Err(_) if self.zim.skip_malformed() => continue,
+                Err(_) => {
+                    self.next = self.max;
+                    return None;
+                }
+            }
         }
+
+        None
     }
 }
@@ -0,0 +1,76 @@
+use crate::directory_entry::DirectoryEntry;
+use crate::directory_iterator::DirectoryIterator;
+use crate::errors::{Error, Result};
+use crate::zim::Zim;
+
+/// Random access to the directory entries of a `Zim`, by URL index.
+///
+/// Created by `Zim::entries`. Entries are parsed on access, so this is cheap to create.
+#[derive(Clone, Copy)]
+pub struct Entries<'a> {
+    zim: &'a Zim,
+}
+
+impl<'a> Entries<'a> {
+    pub(crate) fn new(zim: &'a Zim) -> Self {
+        Entries { zim }
+    }
+
+    /// Number of entries.
+    pub fn len(&self) -> usize {
+        self.zim.url_list.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the entry at URL index `idx`, see `Zim::get_by_url_index`.
+    pub fn get(&self, idx: u32) -> Result<DirectoryEntry> {
+        if idx as usize >= self.len() {
+            return Err(Error::OutOfBounds);
+        }
+
+        self.zim.get_by_url_index(idx)
+    }
+
+    /// Iterates over all entries, sorted by URL.
+    pub fn iter(&self) -> DirectoryIterator<'a> {
+        self.zim.iterate_by_urls()
+    }
+}
+
+impl<'a> IntoIterator for Entries<'a> {
+    type Item = DirectoryEntry;
+    type IntoIter = DirectoryIterator<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_util;
+
+    use super::*;
+
+    #[test]
+    fn test_entries() {
+        let zim = test_util::sample(6).open("entries");
+        let entries = zim.entries();
+        assert_eq!(entries.len(), zim.article_count());
+        assert!(!entries.is_empty());
+
+        assert_eq!(entries.get(0).unwrap().url, "Berlin");
+        assert_eq!(
+            entries.get(3).unwrap().url,
+            zim.get_by_url_index(3).unwrap().url
+        );
+        assert!(matches!(
+            entries.get(entries.len() as u32),
+            Err(Error::OutOfBounds)
+        ));
+        assert_eq!(entries.into_iter().count(), zim.article_count());
+    }
+}
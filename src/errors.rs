@@ -2,12 +2,18 @@ use std::num::TryFromIntError;
 
 use thiserror::Error;
 
+use crate::cluster::Compression;
+
 pub type Result<T> = std::result::Result<T, Error>;
 
 #[derive(Debug, Error)]
 pub enum Error {
     #[error("unknown compression: {0}")]
     UnknownCompression(u8),
+    #[error("unknown compression name: {0}")]
+    UnknownCompressionName(String),
+    #[error("unsupported compression: {0:?}")]
+    UnsupportedCompression(Compression),
     #[error("unknown mimetype")]
     UnknownMimeType,
     #[error("invalid magic number")]
@@ -26,6 +32,12 @@ pub enum Error {
     MissingChecksum,
     #[error("invalid checksum")]
     InvalidChecksum,
+    #[error("invalid archive structure: {0}")]
+    InvalidStructure(String),
+    #[error("invalid entry: {0}")]
+    InvalidEntry(String),
+    #[error("a lock was poisoned by a panicking thread")]
+    LockPoisoned,
     #[error("out of bounds access")]
     OutOfBounds,
     #[error("failed to parse: {0}")]
@@ -34,12 +46,58 @@ pub enum Error {
     TryFromIntError(#[from] TryFromIntError),
 }
 
+impl Error {
+    /// Returns `true` if the error means the archive itself is broken, e.g. because it was
+    /// truncated or damaged during a download, and `false` for IO failures, unsupported
+    /// archives and invalid arguments.
+    ///
+    /// `OutOfBounds` is not considered corruption, as it is also returned for indices passed by
+    /// the caller that are out of range.
+    pub fn is_corruption(&self) -> bool {
+        match self {
+            Error::UnknownCompression(_)
+            | Error::UnknownMimeType
+            | Error::InvalidMagicNumber
+            | Error::InvalidHeader
+            | Error::InvalidNamespace
+            | Error::InvalidClusterExtension
+            | Error::MissingBlobList
+            | Error::MissingChecksum
+            | Error::InvalidChecksum
+            | Error::InvalidStructure(_) => true,
+            Error::InvalidVersion(_)
+            | Error::UnknownCompressionName(_)
+            | Error::InvalidEntry(_)
+            | Error::LockPoisoned
+            | Error::UnsupportedCompression(_)
+            | Error::OutOfBounds
+            | Error::TryFromIntError(_) => false,
+            Error::Parsing(err) => {
+                if let Some(err) = err.downcast_ref::<std::io::Error>() {
+                    matches!(
+                        err.kind(),
+                        std::io::ErrorKind::UnexpectedEof | std::io::ErrorKind::InvalidData
+                    )
+                } else {
+                    err.is::<std::string::FromUtf8Error>() || err.is::<bitreader::BitReaderError>()
+                }
+            }
+        }
+    }
+}
+
 impl From<std::string::FromUtf8Error> for Error {
     fn from(err: std::string::FromUtf8Error) -> Error {
         Error::Parsing(err.into())
     }
 }
 
+impl From<std::str::Utf8Error> for Error {
+    fn from(err: std::str::Utf8Error) -> Error {
+        Error::Parsing(err.into())
+    }
+}
+
 impl From<std::io::Error> for Error {
     fn from(err: std::io::Error) -> Error {
         Error::Parsing(err.into())
@@ -51,3 +109,47 @@ impl From<bitreader::BitReaderError> for Error {
         Error::Parsing(err.into())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Error as IoError, ErrorKind};
+
+    use super::*;
+
+    #[test]
+    fn test_is_corruption() {
+        let corrupt = vec![
+            Error::UnknownCompression(9),
+            Error::UnknownMimeType,
+            Error::InvalidMagicNumber,
+            Error::InvalidHeader,
+            Error::InvalidNamespace,
+            Error::InvalidClusterExtension,
+            Error::MissingBlobList,
+            Error::MissingChecksum,
+            Error::InvalidChecksum,
+            Error::InvalidStructure("bad".into()),
+            IoError::from(ErrorKind::UnexpectedEof).into(),
+            IoError::from(ErrorKind::InvalidData).into(),
+            String::from_utf8(vec![0xff]).unwrap_err().into(),
+        ];
+        for err in corrupt {
+            assert!(err.is_corruption(), "{:?}", err);
+        }
+
+        let not_corrupt = vec![
+            Error::InvalidVersion(4),
+            Error::UnknownCompressionName("gzip".into()),
+            Error::InvalidEntry("missing url".into()),
+            Error::LockPoisoned,
+            Error::UnsupportedCompression(Compression::Bzip2),
+            Error::OutOfBounds,
+            u8::try_from(300u32).unwrap_err().into(),
+            IoError::from(ErrorKind::NotFound).into(),
+            IoError::from(ErrorKind::PermissionDenied).into(),
+        ];
+        for err in not_corrupt {
+            assert!(!err.is_corruption(), "{:?}", err);
+        }
+    }
+}
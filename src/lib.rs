@@ -6,21 +6,41 @@
 //! For more into, see the [OpenZIM website](http://www.openzim.org/wiki/OpenZIM)
 //!
 
+mod blob_list;
+mod cache;
+mod checked;
 mod cluster;
 mod directory_entry;
+mod directory_entry_builder;
 mod directory_iterator;
+mod entries;
 mod errors;
 mod mime_type;
 mod namespace;
+pub mod prelude;
+mod storage;
 mod target;
+#[cfg(test)]
+mod test_util;
 mod uuid;
 mod zim;
+mod zim_set;
 
-pub use crate::cluster::Cluster;
-pub use crate::directory_entry::DirectoryEntry;
+pub use crate::checked::CheckedZim;
+pub use crate::cluster::{parse_cluster_info, BlobReader, Cluster, ClusterReader, Compression};
+pub use crate::directory_entry::{escape_path_segment, DirectoryEntry};
+pub use crate::directory_entry_builder::{DirectoryEntryBuilder, EntryPayload, NewEntry};
+pub use crate::directory_iterator::DirectoryIterator;
+pub use crate::entries::Entries;
 pub use crate::errors::{Error, Result};
 pub use crate::mime_type::MimeType;
 pub use crate::namespace::Namespace;
+pub use crate::storage::{AccessPattern, Storage};
 pub use crate::target::Target;
 pub use crate::uuid::Uuid;
-pub use crate::zim::Zim;
+#[allow(deprecated)]
+pub use crate::zim::ZimOptions;
+pub use crate::zim::{
+    ChecksumAlgorithm, MimeHistogramSource, ParseMode, Validations, Zim, ZimBuilder, ZimStats,
+};
+pub use crate::zim_set::{ArchiveId, ZimSet};
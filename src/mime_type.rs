@@ -1,5 +1,7 @@
+use crate::errors::{Error, Result};
+
 /// Representation of MimeTypes.
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum MimeType {
     /// A special "MimeType" that represents a redirection
     Redirect,
@@ -7,3 +9,53 @@ pub enum MimeType {
     DeletedEntry,
     Type(String),
 }
+
+impl MimeType {
+    /// Resolves a MIME type number, as stored in a directory entry, using the archive's MIME
+    /// type list.
+    ///
+    /// `0xffff`, `0xfffe` and `0xfffd` are the redirect, link target and deleted entry markers,
+    /// any other number must be an index into `list`.
+    pub fn from_raw(index: u16, list: &[String]) -> Result<MimeType> {
+        match index {
+            0xffff => Ok(MimeType::Redirect),
+            0xfffe => Ok(MimeType::LinkTarget),
+            0xfffd => Ok(MimeType::DeletedEntry),
+            index => list
+                .get(index as usize)
+                .map(|mime| MimeType::Type(mime.clone()))
+                .ok_or(Error::UnknownMimeType),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_raw() {
+        let list = vec!["text/html".to_string(), "image/png".to_string()];
+
+        assert_eq!(
+            MimeType::from_raw(0xffff, &list).unwrap(),
+            MimeType::Redirect
+        );
+        assert_eq!(
+            MimeType::from_raw(0xfffe, &list).unwrap(),
+            MimeType::LinkTarget
+        );
+        assert_eq!(
+            MimeType::from_raw(0xfffd, &list).unwrap(),
+            MimeType::DeletedEntry
+        );
+        assert_eq!(
+            MimeType::from_raw(1, &list).unwrap(),
+            MimeType::Type("image/png".into())
+        );
+        assert!(matches!(
+            MimeType::from_raw(2, &list),
+            Err(Error::UnknownMimeType)
+        ));
+    }
+}
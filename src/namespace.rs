@@ -4,7 +4,7 @@ use crate::errors::{Error, Result};
 
 /// Namespaces seperate different types of directory entries - which might have the same title -
 /// stored in the ZIM File Format.
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 #[repr(u8)]
 pub enum Namespace {
     Layout = b'-',
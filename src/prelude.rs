@@ -0,0 +1,23 @@
+//! Re-exports of the most commonly used types.
+//!
+//! ```no_run
+//! use zim::prelude::*;
+//!
+//! # fn main() -> Result<()> {
+//! let zim = Zim::from_bytes(std::fs::read("wikipedia.zim")?)?;
+//! for entry in zim.iterate_by_urls() {
+//!     if let Some(Target::Cluster(cluster, blob)) = entry.target {
+//!         let cluster = zim.get_cluster(cluster)?;
+//!         println!("{}: {} bytes", entry.url, cluster.get_blob(blob)?.len());
+//!     }
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+pub use crate::cluster::Compression;
+pub use crate::errors::{Error, Result};
+pub use crate::mime_type::MimeType;
+pub use crate::namespace::Namespace;
+pub use crate::target::Target;
+pub use crate::zim::Zim;
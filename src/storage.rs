@@ -0,0 +1,105 @@
+use std::fmt;
+use std::ops::Deref;
+use std::ops::Range;
+
+#[cfg(feature = "mmap")]
+use memmap::Mmap;
+
+use crate::errors::Result;
+
+/// How an archive is going to be read, see `Zim::set_access_pattern`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AccessPattern {
+    /// No particular pattern, the default.
+    Normal,
+    /// Mostly front to back, e.g. extracting everything. The kernel reads ahead aggressively
+    /// and may drop pages soon after they were read.
+    Sequential,
+    /// Scattered reads, e.g. serving requests. The kernel reads ahead less.
+    Random,
+    /// The whole archive will be needed soon, so the kernel starts reading it in.
+    WillNeed,
+}
+
+/// The bytes backing a ZIM archive.
+///
+/// All parsing works on a plain `[u8]` slice, so the archive can either be memory mapped from a
+/// file (requires the `mmap` feature) or held in memory.
+pub enum Storage {
+    /// A memory mapped file.
+    #[cfg(feature = "mmap")]
+    Mmap(Mmap),
+    /// An archive held in memory.
+    Bytes(Vec<u8>),
+}
+
+impl Storage {
+    /// Tells the kernel how the given range is going to be read.
+    ///
+    /// Does nothing for archives held in memory or on non-unix platforms.
+    pub(crate) fn advise(&self, range: Range<usize>, pattern: AccessPattern) -> Result<()> {
+        #[cfg(all(feature = "mmap", unix))]
+        if let Storage::Mmap(map) = self {
+            let end = range.end.min(map.len());
+            if range.start < end {
+                let advice = match pattern {
+                    AccessPattern::Normal => libc::MADV_NORMAL,
+                    AccessPattern::Sequential => libc::MADV_SEQUENTIAL,
+                    AccessPattern::Random => libc::MADV_RANDOM,
+                    AccessPattern::WillNeed => libc::MADV_WILLNEED,
+                };
+                // madvise needs a page aligned start address. The mapping may start in the middle
+                // of a page if it was created with an offset, but the whole page is still mapped.
+                let page = unsafe { libc::sysconf(libc::_SC_PAGESIZE) }.max(1) as usize;
+                let addr = map.as_ptr() as usize + range.start;
+                let aligned = addr / page * page;
+                let ret = unsafe {
+                    libc::madvise(
+                        aligned as *mut libc::c_void,
+                        end - range.start + (addr - aligned),
+                        advice,
+                    )
+                };
+                if ret != 0 {
+                    return Err(std::io::Error::last_os_error().into());
+                }
+            }
+        }
+        #[cfg(not(all(feature = "mmap", unix)))]
+        let _ = (range, pattern);
+
+        Ok(())
+    }
+}
+
+impl Deref for Storage {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        match self {
+            #[cfg(feature = "mmap")]
+            Storage::Mmap(map) => map,
+            Storage::Bytes(bytes) => bytes,
+        }
+    }
+}
+
+impl AsRef<[u8]> for Storage {
+    fn as_ref(&self) -> &[u8] {
+        self
+    }
+}
+
+impl fmt::Debug for Storage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind = match self {
+            #[cfg(feature = "mmap")]
+            Storage::Mmap(_) => "Mmap",
+            Storage::Bytes(_) => "Bytes",
+        };
+        f.debug_struct("Storage")
+            .field("kind", &kind)
+            .field("len", &self.len())
+            .finish()
+    }
+}
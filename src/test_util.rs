@@ -0,0 +1,417 @@
+//! Helpers to build small, synthetic ZIM archives for tests.
+//!
+//! The archives are laid out the same way real writers do it: header, mime list, url and title
+//! pointer lists, directory entries, cluster pointer list, clusters and finally the checksum.
+#![allow(dead_code)]
+
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use md5::{Digest, Md5};
+
+use crate::cluster::Compression;
+use crate::zim::Zim;
+
+static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+enum Content {
+    Blob(u32, u32),
+    Redirect(char, String),
+    Mime(u16),
+}
+
+struct Entry {
+    namespace: char,
+    url: Vec<u8>,
+    title: Vec<u8>,
+    mime: u16,
+    content: Content,
+    parameters: Vec<u8>,
+}
+
+struct TestCluster {
+    compression: Compression,
+    extended: bool,
+    blobs: Vec<Vec<u8>>,
+}
+
+pub struct ArchiveBuilder {
+    version_major: u16,
+    version_minor: u16,
+    uuid: [u8; 16],
+    mime_types: Vec<String>,
+    entries: Vec<Entry>,
+    clusters: Vec<TestCluster>,
+    main_page: Option<(char, String)>,
+    layout_page: Option<(char, String)>,
+}
+
+impl ArchiveBuilder {
+    pub fn new(version_major: u16) -> Self {
+        ArchiveBuilder {
+            version_major,
+            version_minor: 0,
+            uuid: [7; 16],
+            mime_types: Vec::new(),
+            entries: Vec::new(),
+            clusters: Vec::new(),
+            main_page: None,
+            layout_page: None,
+        }
+    }
+
+    pub fn minor(mut self, version_minor: u16) -> Self {
+        self.version_minor = version_minor;
+        self
+    }
+
+    pub fn uuid(mut self, uuid: [u8; 16]) -> Self {
+        self.uuid = uuid;
+        self
+    }
+
+    /// Registers a mime type, returning its index.
+    pub fn mime(&mut self, mime: &str) -> u16 {
+        match self.mime_types.iter().position(|m| m == mime) {
+            Some(idx) => idx as u16,
+            None => {
+                self.mime_types.push(mime.to_string());
+                (self.mime_types.len() - 1) as u16
+            }
+        }
+    }
+
+    /// Adds a cluster, returning its index.
+    pub fn cluster(&mut self, compression: Compression, blobs: Vec<Vec<u8>>) -> u32 {
+        self.clusters.push(TestCluster {
+            compression,
+            extended: false,
+            blobs,
+        });
+        (self.clusters.len() - 1) as u32
+    }
+
+    /// Adds an extended cluster (8 byte offsets), returning its index.
+    pub fn extended_cluster(&mut self, compression: Compression, blobs: Vec<Vec<u8>>) -> u32 {
+        let idx = self.cluster(compression, blobs);
+        self.clusters[idx as usize].extended = true;
+        idx
+    }
+
+    /// Adds an entry pointing to the given blob.
+    pub fn article(
+        &mut self,
+        namespace: char,
+        url: &str,
+        title: &str,
+        mime: &str,
+        cluster: u32,
+        blob: u32,
+    ) -> &mut Self {
+        self.raw_article(
+            namespace,
+            url.as_bytes(),
+            title.as_bytes(),
+            mime,
+            cluster,
+            blob,
+        )
+    }
+
+    /// Adds an entry with arbitrary url and title bytes.
+    pub fn raw_article(
+        &mut self,
+        namespace: char,
+        url: &[u8],
+        title: &[u8],
+        mime: &str,
+        cluster: u32,
+        blob: u32,
+    ) -> &mut Self {
+        let mime = self.mime(mime);
+        self.entries.push(Entry {
+            namespace,
+            url: url.to_vec(),
+            title: title.to_vec(),
+            mime,
+            content: Content::Blob(cluster, blob),
+            parameters: Vec::new(),
+        });
+        self
+    }
+
+    /// Adds a redirect to the entry found at `target` (namespace, url).
+    pub fn redirect(
+        &mut self,
+        namespace: char,
+        url: &str,
+        title: &str,
+        target: (char, &str),
+    ) -> &mut Self {
+        self.entries.push(Entry {
+            namespace,
+            url: url.as_bytes().to_vec(),
+            title: title.as_bytes().to_vec(),
+            mime: 0xffff,
+            content: Content::Redirect(target.0, target.1.to_string()),
+            parameters: Vec::new(),
+        });
+        self
+    }
+
+    /// Adds an entry using one of the special mime types without a target (link target or deleted).
+    pub fn special(&mut self, namespace: char, url: &str, mime: u16) -> &mut Self {
+        self.entries.push(Entry {
+            namespace,
+            url: url.as_bytes().to_vec(),
+            title: Vec::new(),
+            mime,
+            content: Content::Mime(mime),
+            parameters: Vec::new(),
+        });
+        self
+    }
+
+    /// Sets the extra parameters of the most recently added entry.
+    pub fn parameters(&mut self, parameters: &[u8]) -> &mut Self {
+        self.entries.last_mut().unwrap().parameters = parameters.to_vec();
+        self
+    }
+
+    pub fn main_page(&mut self, namespace: char, url: &str) -> &mut Self {
+        self.main_page = Some((namespace, url.to_string()));
+        self
+    }
+
+    pub fn layout_page(&mut self, namespace: char, url: &str) -> &mut Self {
+        self.layout_page = Some((namespace, url.to_string()));
+        self
+    }
+
+    /// Serializes the archive.
+    pub fn build(mut self) -> Vec<u8> {
+        self.entries
+            .sort_by(|a, b| (a.namespace, &a.url).cmp(&(b.namespace, &b.url)));
+        let url_index = |ns: char, url: &str| -> u32 {
+            self.entries
+                .iter()
+                .position(|e| e.namespace == ns && e.url == url.as_bytes())
+                .expect("unknown entry") as u32
+        };
+
+        let mut title_order: Vec<u32> = (0..self.entries.len() as u32).collect();
+        title_order.sort_by(|a, b| {
+            let a = &self.entries[*a as usize];
+            let b = &self.entries[*b as usize];
+            let ta = if a.title.is_empty() { &a.url } else { &a.title };
+            let tb = if b.title.is_empty() { &b.url } else { &b.title };
+            (a.namespace, ta).cmp(&(b.namespace, tb))
+        });
+
+        let mut mime_list = Vec::new();
+        for mime in &self.mime_types {
+            mime_list.extend_from_slice(mime.as_bytes());
+            mime_list.push(0);
+        }
+        mime_list.push(0);
+
+        let mut dirents = Vec::new();
+        for entry in &self.entries {
+            let mut raw = Vec::new();
+            raw.extend_from_slice(&entry.mime.to_le_bytes());
+            raw.push(entry.parameters.len() as u8);
+            raw.push(entry.namespace as u8);
+            raw.extend_from_slice(&0u32.to_le_bytes()); // revision
+            match &entry.content {
+                Content::Blob(cluster, blob) => {
+                    raw.extend_from_slice(&cluster.to_le_bytes());
+                    raw.extend_from_slice(&blob.to_le_bytes());
+                }
+                Content::Redirect(ns, url) => {
+                    raw.extend_from_slice(&url_index(*ns, url).to_le_bytes());
+                }
+                Content::Mime(_) => {}
+            }
+            raw.extend_from_slice(&entry.url);
+            raw.push(0);
+            raw.extend_from_slice(&entry.title);
+            raw.push(0);
+            raw.extend_from_slice(&entry.parameters);
+            dirents.push(raw);
+        }
+
+        let clusters: Vec<Vec<u8>> = self.clusters.iter().map(encode_cluster).collect();
+
+        let count = self.entries.len() as u64;
+        let mime_list_pos = 80u64;
+        let url_ptr_pos = mime_list_pos + mime_list.len() as u64;
+        let title_ptr_pos = url_ptr_pos + count * 8;
+        let dirent_pos = title_ptr_pos + count * 4;
+        let dirents_len: u64 = dirents.iter().map(|d| d.len() as u64).sum();
+        let cluster_ptr_pos = dirent_pos + dirents_len;
+        let clusters_pos = cluster_ptr_pos + clusters.len() as u64 * 8;
+        let clusters_len: u64 = clusters.iter().map(|c| c.len() as u64).sum();
+        let checksum_pos = clusters_pos + clusters_len;
+
+        let main_page = self
+            .main_page
+            .as_ref()
+            .map(|(ns, url)| url_index(*ns, url))
+            .unwrap_or(0xffffffff);
+        let layout_page = self
+            .layout_page
+            .as_ref()
+            .map(|(ns, url)| url_index(*ns, url))
+            .unwrap_or(0xffffffff);
+
+        let mut out = Vec::new();
+        out.extend_from_slice(&crate::zim::ZIM_MAGIC_NUMBER.to_le_bytes());
+        out.extend_from_slice(&self.version_major.to_le_bytes());
+        out.extend_from_slice(&self.version_minor.to_le_bytes());
+        out.extend_from_slice(&self.uuid);
+        out.extend_from_slice(&(count as u32).to_le_bytes());
+        out.extend_from_slice(&(clusters.len() as u32).to_le_bytes());
+        out.extend_from_slice(&url_ptr_pos.to_le_bytes());
+        out.extend_from_slice(&title_ptr_pos.to_le_bytes());
+        out.extend_from_slice(&cluster_ptr_pos.to_le_bytes());
+        out.extend_from_slice(&mime_list_pos.to_le_bytes());
+        out.extend_from_slice(&main_page.to_le_bytes());
+        out.extend_from_slice(&layout_page.to_le_bytes());
+        out.extend_from_slice(&checksum_pos.to_le_bytes());
+        assert_eq!(out.len(), 80);
+
+        out.extend_from_slice(&mime_list);
+        let mut pos = dirent_pos;
+        for d in &dirents {
+            out.extend_from_slice(&pos.to_le_bytes());
+            pos += d.len() as u64;
+        }
+        for idx in &title_order {
+            out.extend_from_slice(&idx.to_le_bytes());
+        }
+        for d in &dirents {
+            out.extend_from_slice(d);
+        }
+        let mut pos = clusters_pos;
+        for c in &clusters {
+            out.extend_from_slice(&pos.to_le_bytes());
+            pos += c.len() as u64;
+        }
+        for c in &clusters {
+            out.extend_from_slice(c);
+        }
+        assert_eq!(out.len() as u64, checksum_pos);
+
+        let checksum = Md5::digest(&out);
+        out.extend_from_slice(&checksum);
+        out
+    }
+
+    /// Serializes and opens the archive, memory mapping a temporary file if the `mmap` feature
+    /// is enabled.
+    pub fn open(self, name: &str) -> Zim {
+        #[cfg(feature = "mmap")]
+        return Zim::new(self.write(name)).unwrap();
+        #[cfg(not(feature = "mmap"))]
+        {
+            let _ = name;
+            Zim::from_bytes(self.build()).unwrap()
+        }
+    }
+
+    /// Serializes the archive and writes it to a fresh temporary file.
+    pub fn write(self, name: &str) -> PathBuf {
+        write_temp(name, &self.build())
+    }
+}
+
+/// Writes `data` to a fresh temporary file.
+pub fn write_temp(name: &str, data: &[u8]) -> PathBuf {
+    let path = std::env::temp_dir().join(format!(
+        "zim-test-{}-{}-{}.zim",
+        std::process::id(),
+        COUNTER.fetch_add(1, Ordering::SeqCst),
+        name
+    ));
+    let mut f = std::fs::File::create(&path).expect("failed to create test archive");
+    f.write_all(data).expect("failed to write test archive");
+    path
+}
+
+/// Serializes a cluster: info byte, followed by the (possibly compressed) offsets and blobs.
+fn encode_cluster(cluster: &TestCluster) -> Vec<u8> {
+    raw_cluster(cluster.compression, cluster.extended, &cluster.blobs)
+}
+
+pub fn raw_cluster(compression: Compression, extended: bool, blobs: &[Vec<u8>]) -> Vec<u8> {
+    let offset_size = if extended { 8 } else { 4 };
+    let mut data = Vec::new();
+    let mut offset = ((blobs.len() + 1) * offset_size) as u64;
+    let mut offsets = vec![offset];
+    for blob in blobs {
+        offset += blob.len() as u64;
+        offsets.push(offset);
+    }
+    for offset in offsets {
+        if extended {
+            data.extend_from_slice(&offset.to_le_bytes());
+        } else {
+            data.extend_from_slice(&(offset as u32).to_le_bytes());
+        }
+    }
+    for blob in blobs {
+        data.extend_from_slice(blob);
+    }
+
+    let info = u8::from(compression) | if extended { 0x10 } else { 0 };
+    let mut out = vec![info];
+    match compression {
+        Compression::None => out.extend_from_slice(&data),
+        Compression::Lzma2 => {
+            let mut encoder = xz2::write::XzEncoder::new(Vec::new(), 6);
+            encoder.write_all(&data).unwrap();
+            out.extend_from_slice(&encoder.finish().unwrap());
+        }
+        Compression::Zstd => {
+            out.extend_from_slice(&zstd::stream::encode_all(&data[..], 3).unwrap())
+        }
+        // no encoders available, store the raw data
+        Compression::Zlib | Compression::Bzip2 => out.extend_from_slice(&data),
+    }
+    out
+}
+
+/// A small archive with a bit of everything: metadata, articles, an image, a redirect and a
+/// main page, spread over an uncompressed and a compressed cluster.
+pub fn sample(version_major: u16) -> ArchiveBuilder {
+    let mut b = ArchiveBuilder::new(version_major);
+    let compression = if version_major == 6 {
+        Compression::Zstd
+    } else {
+        Compression::Lzma2
+    };
+    let text = b.cluster(
+        compression,
+        vec![
+            b"<html>Berlin</html>".to_vec(),
+            b"<html>New York</html>".to_vec(),
+            b"<html>Main</html>".to_vec(),
+            b"Sample".to_vec(),
+            b"en".to_vec(),
+        ],
+    );
+    let images = b.cluster(
+        Compression::None,
+        vec![vec![0x89, b'P', b'N', b'G', 0, 1, 2]],
+    );
+    b.article('A', "Berlin", "Berlin", "text/html", text, 0)
+        .article('A', "New York", "New York", "text/html", text, 1)
+        .article('A', "Main_Page", "Main Page", "text/html", text, 2)
+        .redirect('A', "NYC", "NYC", ('A', "New York"))
+        .article('I', "logo.png", "", "image/png", images, 0)
+        .article('M', "Title", "", "text/plain", text, 3)
+        .article('M', "Language", "", "text/plain", text, 4)
+        .main_page('A', "Main_Page");
+    b
+}
@@ -2,6 +2,7 @@ use std::fmt::{self, Debug};
 
 const HEX: &[u8] = b"0123456789abcdef";
 
+#[derive(Clone, Copy, PartialEq, Eq)]
 pub struct Uuid([u8; 16]);
 
 impl Uuid {
@@ -1,31 +1,63 @@
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
+#[cfg(feature = "mmap")]
 use std::fs::File;
+use std::io::BufRead;
 use std::io::Cursor;
-use std::io::{BufRead, BufReader, Read};
-use std::path::{Path, PathBuf};
+use std::io::Read;
+#[cfg(feature = "tar")]
+use std::io::Write;
+use std::ops::Range;
+#[cfg(feature = "mmap")]
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::thread::JoinHandle;
 
 use byteorder::{LittleEndian, ReadBytesExt};
 use md5::digest::OutputSizeUser;
 use md5::{digest::generic_array::GenericArray, Digest, Md5};
-use memmap::Mmap;
+#[cfg(feature = "mmap")]
+use memmap::{Mmap, MmapOptions};
+use percent_encoding::percent_decode_str;
+#[cfg(feature = "rand")]
+use rand::{seq::IteratorRandom, Rng};
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+use sha2::Sha256;
 
-use crate::cluster::Cluster;
+use crate::cache::{ClusterCache, DEFAULT_CACHE_SIZE};
+use crate::cluster::{parse_cluster_info, BlobReader, Cluster, Compression};
+#[cfg(feature = "tar")]
+use crate::directory_entry::escape_path_segment;
 use crate::directory_entry::DirectoryEntry;
 use crate::directory_iterator::DirectoryIterator;
+use crate::entries::Entries;
 use crate::errors::{Error, Result};
 use crate::mime_type::MimeType;
+use crate::namespace::Namespace;
+use crate::storage::{AccessPattern, Storage};
+use crate::target::Target;
 use crate::uuid::Uuid;
 
 /// Magic number to recognise the file format, must be 72173914
 pub const ZIM_MAGIC_NUMBER: u32 = 72173914;
 
+/// Size in bytes of the fixed part of the header, the same for major version 5 and 6.
+pub const ZIM_HEADER_SIZE: usize = 80;
+
 /// Represents a ZIM file
+///
+/// A `Zim` is `Send + Sync`: the file is only ever read, so a single instance can be shared by
+/// reference between threads, which then fetch and decompress clusters concurrently.
 #[allow(dead_code)]
 pub struct Zim {
     // Zim structure data:
     pub header: ZimHeader,
 
-    pub master_view: Mmap,
-    /// The path to the file.
+    pub master_view: Arc<Storage>,
+    /// The path to the file, empty for archives loaded from memory.
     pub file_path: PathBuf,
 
     /// List of mimetypes used in this ZIM archive
@@ -36,12 +68,50 @@ pub struct Zim {
 
     /// MD5 checksum.
     pub checksum: Checksum,
+
+    /// Decompressed clusters, shared between all `Cluster`s of this archive.
+    cache: Arc<ClusterCache>,
+    /// Maps `(cluster, blob)` to the URL index of the first entry stored there, built on first
+    /// use by `entry_for_blob`.
+    blob_index: Arc<OnceLock<HashMap<(u32, u32), u32>>>,
+    /// Number of redirects and content entries, counted on first use.
+    target_counts: Arc<OnceLock<(u32, u32)>>,
+    /// The settings the archive was opened with, reused by `reopen`. `set_parse_mode` keeps
+    /// `parse_mode` up to date.
+    settings: ZimBuilder,
+    /// Number of malformed entries skipped in lenient mode.
+    skipped_entries: Arc<AtomicU64>,
 }
 
 pub type Checksum = GenericArray<u8, <Md5 as OutputSizeUser>::OutputSize>;
 
+/// The digest used for the archive checksum, see `Zim::checksum_algorithm`.
+///
+/// All current ZIM versions use MD5. New variants may be added for future formats.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ChecksumAlgorithm {
+    Md5,
+}
+
+impl ChecksumAlgorithm {
+    /// The algorithm used by archives with the given major version.
+    pub fn for_version(_version_major: u16) -> ChecksumAlgorithm {
+        ChecksumAlgorithm::Md5
+    }
+}
+
+/// Where the counts of `Zim::mime_histogram` come from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MimeHistogramSource {
+    /// The `M/Counter` metadata entry.
+    Counter,
+    /// A scan over all entries.
+    Scan,
+}
+
 /// A ZIM file starts with a header.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct ZimHeader {
     /// Major version, either 5 or 6
     pub version_major: u16,
@@ -72,16 +142,386 @@ pub struct ZimHeader {
     pub geo_index_pos: Option<u64>,
 }
 
+/// Options for opening a Zim file with `Zim::open_ro_shared`.
+///
+/// Superseded by `ZimBuilder`, which has the same settings and more. Note that the defaults
+/// differ: `ZimOptions::default()` runs the `Validations::STRUCTURE` checks on open, while
+/// `ZimBuilder::new()` runs none, like `Zim::new`.
+#[deprecated(note = "use `ZimBuilder` instead")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ZimOptions {
+    /// Ask the kernel to read the header, MIME type list and pointer lists ahead of time, which
+    /// reduces the latency of the first lookups on a cold page cache.
+    pub prefetch: bool,
+    /// How iterating over all entries deals with malformed entries.
+    pub parse_mode: ParseMode,
+    /// The checks of `Zim::validate_with` to run before returning the archive. By default the
+    /// cheap structural checks run, but not the checksum.
+    pub validate_on_open: Validations,
+}
+
+/// Configures how a Zim archive is opened.
+///
+/// The defaults open an archive exactly like `Zim::new`: a cache of `DEFAULT_CACHE_SIZE` bytes,
+/// strict parsing, no validation beyond what parsing the header requires and no access pattern
+/// or prefetching.
+///
+/// ```no_run
+/// use zim::{ParseMode, Result, Validations, ZimBuilder};
+///
+/// # fn main() -> Result<()> {
+/// let zim = ZimBuilder::new()
+///     .cache_size(256 * 1024 * 1024)
+///     .parse_mode(ParseMode::Lenient)
+///     .validate_on_open(Validations::STRUCTURE)
+///     .from_bytes(std::fs::read("wikipedia.zim")?)?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct ZimBuilder {
+    cache_size: usize,
+    parse_mode: ParseMode,
+    validate_on_open: Validations,
+    access_pattern: Option<AccessPattern>,
+    prefetch: bool,
+}
+
+impl Default for ZimBuilder {
+    fn default() -> Self {
+        ZimBuilder {
+            cache_size: DEFAULT_CACHE_SIZE,
+            parse_mode: ParseMode::default(),
+            validate_on_open: Validations::empty(),
+            access_pattern: None,
+            prefetch: false,
+        }
+    }
+}
+
+impl ZimBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the memory budget in bytes for decompressed clusters, see `Zim::cache_size`.
+    pub fn cache_size(mut self, bytes: usize) -> Self {
+        self.cache_size = bytes;
+        self
+    }
+
+    /// Sets how iterating over all entries deals with malformed entries.
+    pub fn parse_mode(mut self, parse_mode: ParseMode) -> Self {
+        self.parse_mode = parse_mode;
+        self
+    }
+
+    /// Sets the checks of `Zim::validate_with` to run before returning the archive.
+    pub fn validate_on_open(mut self, checks: Validations) -> Self {
+        self.validate_on_open = checks;
+        self
+    }
+
+    /// Sets how the archive is going to be read, see `Zim::set_access_pattern`.
+    pub fn access_pattern(mut self, pattern: AccessPattern) -> Self {
+        self.access_pattern = Some(pattern);
+        self
+    }
+
+    /// Asks the kernel to read the header, MIME type list and pointer lists ahead of time, which
+    /// reduces the latency of the first lookups on a cold page cache.
+    pub fn prefetch(mut self, prefetch: bool) -> Self {
+        self.prefetch = prefetch;
+        self
+    }
+
+    /// Memory maps and loads the Zim file at `p`.
+    #[cfg(feature = "mmap")]
+    pub fn open<P: AsRef<Path>>(self, p: P) -> Result<Zim> {
+        let f = File::open(p.as_ref())?;
+        let master_view = Storage::Mmap(unsafe { Mmap::map(&f)? });
+
+        self.load(master_view, p.as_ref().into())
+    }
+
+    /// Loads a Zim archive held in memory.
+    pub fn from_bytes(self, data: Vec<u8>) -> Result<Zim> {
+        self.load(Storage::Bytes(data), PathBuf::new())
+    }
+
+    fn load(self, master_view: Storage, file_path: PathBuf) -> Result<Zim> {
+        if self.prefetch {
+            let (header, mime_table) = parse_header(&master_view)?;
+            let mime_len: u64 = mime_table.iter().map(|m| m.len() as u64 + 1).sum::<u64>() + 1;
+            let count = header.article_count as u64;
+            let regions = [
+                (0, header.size() as u64),
+                (header.mime_list_pos, mime_len),
+                (header.url_ptr_pos, count * 8),
+                (header.title_ptr_pos, count * 4),
+                (header.cluster_ptr_pos, header.cluster_count as u64 * 8),
+            ];
+            for (start, len) in regions {
+                let end = start.saturating_add(len);
+                // only a hint, failing to give it doesn't matter
+                let _ = master_view.advise(start as usize..end as usize, AccessPattern::WillNeed);
+            }
+        }
+
+        let zim = Zim::from_storage(master_view, file_path, self)?;
+        if let Some(pattern) = self.access_pattern {
+            zim.set_access_pattern(pattern)?;
+        }
+        zim.validate_with(self.validate_on_open)?;
+
+        Ok(zim)
+    }
+}
+
+/// A set of checks for `Zim::validate_with`, combined with `|`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Validations(u8);
+
+impl Validations {
+    /// The magic number at the start of the file.
+    pub const MAGIC: Validations = Validations(1);
+    /// The major version is one this crate can read.
+    pub const VERSION: Validations = Validations(1 << 1);
+    /// The MIME type list, the pointer lists, the main and layout page and the checksum
+    /// position lie within the file, and the pointer lists match the entry count.
+    pub const POINTER_BOUNDS: Validations = Validations(1 << 2);
+    /// The cluster offsets are increasing and within the file.
+    pub const CLUSTER_MONOTONIC: Validations = Validations(1 << 3);
+    /// The MD5 checksum, which reads the whole file.
+    pub const CHECKSUM: Validations = Validations(1 << 4);
+    /// No two entries have the same namespace and URL, which reads all entries.
+    pub const UNIQUE_URLS: Validations = Validations(1 << 5);
+
+    /// All checks that only read the header and pointer lists.
+    pub const STRUCTURE: Validations = Validations(0b1111);
+    pub const ALL: Validations = Validations(0b111111);
+
+    pub const fn empty() -> Validations {
+        Validations(0)
+    }
+
+    pub const fn contains(self, other: Validations) -> bool {
+        self.0 & other.0 == other.0
+    }
+}
+
+impl Default for Validations {
+    fn default() -> Self {
+        Validations::STRUCTURE
+    }
+}
+
+impl std::ops::BitOr for Validations {
+    type Output = Validations;
+
+    fn bitor(self, rhs: Validations) -> Validations {
+        Validations(self.0 | rhs.0)
+    }
+}
+
+/// How iterating over all entries deals with an entry that fails to parse.
+///
+/// Applies to `iterate_by_urls`, `iterate_by_urls_lenient`, `entries_by_cluster`,
+/// `par_map_entries`, `articles`, `layout_resources` and everything built on them, like `extract_to_tar`. Looking up a single
+/// entry always returns the error.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ParseMode {
+    /// Stop at the malformed entry: iterators end early and `entries_by_cluster` returns the
+    /// error.
+    #[default]
+    Strict,
+    /// Skip the malformed entry and continue. Nothing is logged, skipped entries are
+    /// counted in `ZimStats::entries_skipped`.
+    Lenient,
+}
+
+/// Counters for monitoring a `Zim`, mostly its cluster cache, returned by `Zim::stats`.
+///
+/// The counters are shared by all handles created with `Zim::try_clone`. Uncompressed clusters
+/// never go through the cache and aren't counted.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ZimStats {
+    /// Lookups that found decompressed cluster data in the cache.
+    pub cache_hits: u64,
+    /// Lookups that didn't.
+    pub cache_misses: u64,
+    /// Number of times a cluster was decompressed.
+    pub clusters_decompressed: u64,
+    /// Total size of the decompressed data.
+    pub bytes_decompressed: u64,
+    /// Malformed entries skipped in `ParseMode::Lenient`.
+    pub entries_skipped: u64,
+}
+
+impl ZimHeader {
+    /// Size of the header in bytes.
+    ///
+    /// Both major version 5 and 6 use an 80 byte header. Some old archives additionally store
+    /// the geo index position, growing the header to 88 bytes.
+    pub fn size(&self) -> usize {
+        if self.geo_index_pos.is_some() {
+            ZIM_HEADER_SIZE + 8
+        } else {
+            ZIM_HEADER_SIZE
+        }
+    }
+
+    /// Checks that the positions in the header are possible for a file of `file_len` bytes.
+    ///
+    /// The checksum has to fit into the file, and the MIME type list and the pointer lists have
+    /// to lie before it. Returns `Error::InvalidHeader` otherwise.
+    pub fn validate_against_len(&self, file_len: u64) -> Result<()> {
+        let fits = |pos: u64, len: u64, end: u64| pos.checked_add(len).is_some_and(|e| e <= end);
+
+        if !fits(self.checksum_pos, 16, file_len) {
+            return Err(Error::InvalidHeader);
+        }
+        let end = self.checksum_pos;
+        let articles = self.article_count as u64;
+        let regions = [
+            (self.mime_list_pos, 1),
+            (self.url_ptr_pos, articles * 8),
+            (self.title_ptr_pos, articles * 4),
+            (self.cluster_ptr_pos, self.cluster_count as u64 * 8),
+        ];
+        if regions.iter().all(|(pos, len)| fits(*pos, *len, end)) {
+            Ok(())
+        } else {
+            Err(Error::InvalidHeader)
+        }
+    }
+}
+
 impl Zim {
     /// Loads a Zim file
     ///
     /// Loads a Zim file and parses the header, and the url, title, and cluster offset tables.  The
     /// rest of the data isn't parsed until it's needed, so this should be fairly quick.
+    #[cfg(feature = "mmap")]
     pub fn new<P: AsRef<Path>>(p: P) -> Result<Zim> {
         let f = File::open(p.as_ref())?;
-        let master_view = unsafe { Mmap::map(&f)? };
+        let mut zim = Zim::from_mmap(unsafe { Mmap::map(&f)? })?;
+        zim.file_path = p.as_ref().into();
+
+        Ok(zim)
+    }
+
+    /// Loads a Zim file like `new`, and verifies its checksum on a background thread.
+    ///
+    /// The archive can be used right away, `on_done` is called with the result of
+    /// `verify_checksum` once it is known. Anything read before then is trusted without being
+    /// verified.
+    #[cfg(feature = "mmap")]
+    pub fn open_verified_lazy<P, F>(p: P, on_done: F) -> Result<Zim>
+    where
+        P: AsRef<Path>,
+        F: FnOnce(Result<()>) + Send + 'static,
+    {
+        let zim = Zim::new(p)?;
+        zim.verify_checksum_in_background(on_done);
+
+        Ok(zim)
+    }
+
+    /// Loads a Zim archive from an existing memory mapping.
+    ///
+    /// This leaves the mapping policy to the caller, e.g. mapping only a region of a larger file.
+    /// The mapping must start at the beginning of the archive. As no path is known, `file_path`
+    /// is empty.
+    #[cfg(feature = "mmap")]
+    pub fn from_mmap(map: Mmap) -> Result<Zim> {
+        Zim::from_storage(Storage::Mmap(map), PathBuf::new(), ZimBuilder::default())
+    }
+
+    /// Maps the file at `file_path` again, to pick up a file that was replaced on disk.
+    ///
+    /// The header and pointer lists are parsed again and the cluster cache is reset. The archive
+    /// is opened with the same settings as before, e.g. from `ZimBuilder`, including the parse
+    /// mode set with `set_parse_mode`. Any
+    /// `Cluster` or `Blob` borrowed from the old mapping must be dropped first, which `&mut self`
+    /// ensures. Handles created with `try_clone` keep using the old file. The whole file is
+    /// mapped, so this isn't suitable for archives opened with `open_at`.
+    #[cfg(feature = "mmap")]
+    pub fn reopen(&mut self) -> Result<()> {
+        if self.file_path.as_os_str().is_empty() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "archive was not loaded from a file",
+            )
+            .into());
+        }
+
+        *self = self.settings.open(&self.file_path)?;
+
+        Ok(())
+    }
+
+    /// Loads a Zim archive stored at `offset` within a larger file.
+    ///
+    /// Only the region of the archive is mapped, `len` bytes or up to the end of the file, and
+    /// all positions within the archive are relative to `offset`.
+    #[cfg(feature = "mmap")]
+    pub fn open_at<P: AsRef<Path>>(p: P, offset: u64, len: Option<u64>) -> Result<Zim> {
+        let f = File::open(p.as_ref())?;
+        let file_len = f.metadata()?.len();
+        let len = match len {
+            Some(len) => len,
+            None => file_len.checked_sub(offset).ok_or(Error::OutOfBounds)?,
+        };
+        if offset.checked_add(len).is_none_or(|end| end > file_len) {
+            return Err(Error::OutOfBounds);
+        }
+
+        let map = unsafe {
+            MmapOptions::new()
+                .offset(offset)
+                .len(usize::try_from(len)?)
+                .map(&f)?
+        };
+        let mut zim = Zim::from_mmap(map)?;
+        zim.file_path = p.as_ref().into();
+
+        Ok(zim)
+    }
 
+    /// Loads a Zim file using a read-only, shared memory mapping.
+    ///
+    /// The file is mapped with `MAP_SHARED`, the same as `new` does. Because the mapping is
+    /// backed directly by the page cache, all processes that open the same file share a single
+    /// copy of the pages they read, so running many worker processes doesn't multiply the
+    /// memory used. Nothing is ever written through the mapping.
+    ///
+    /// With `prefetch` set, the regions needed for lookups are requested with
+    /// `madvise(MADV_WILLNEED)` before they are parsed.
+    ///
+    /// Superseded by `ZimBuilder::open`, which uses the same mapping. See `ZimOptions` for how
+    /// the defaults differ.
+    #[cfg(feature = "mmap")]
+    #[deprecated(note = "use `ZimBuilder::open` instead")]
+    #[allow(deprecated)]
+    pub fn open_ro_shared<P: AsRef<Path>>(p: P, options: ZimOptions) -> Result<Zim> {
+        ZimBuilder::new()
+            .prefetch(options.prefetch)
+            .parse_mode(options.parse_mode)
+            .validate_on_open(options.validate_on_open)
+            .open(p)
+    }
+
+    /// Loads a Zim archive held in memory.
+    ///
+    /// Unlike `new`, this doesn't need the `mmap` feature.
+    pub fn from_bytes(data: Vec<u8>) -> Result<Zim> {
+        Zim::from_storage(Storage::Bytes(data), PathBuf::new(), ZimBuilder::default())
+    }
+
+    fn from_storage(master_view: Storage, file_path: PathBuf, settings: ZimBuilder) -> Result<Zim> {
         let (header, mime_table) = parse_header(&master_view)?;
+        header.validate_against_len(master_view.len() as u64)?;
         let url_list = parse_url_list(&master_view, header.url_ptr_pos, header.article_count)?;
         let article_list =
             parse_article_list(&master_view, header.title_ptr_pos, header.article_count)?;
@@ -93,294 +533,3166 @@ impl Zim {
 
         Ok(Zim {
             header,
-            file_path: p.as_ref().into(),
-            master_view,
+            file_path,
+            master_view: Arc::new(master_view),
             mime_table,
             url_list,
             article_list,
             cluster_list,
             checksum,
+            cache: Arc::new(ClusterCache::new(settings.cache_size)),
+            blob_index: Arc::new(OnceLock::new()),
+            target_counts: Arc::new(OnceLock::new()),
+            settings,
+            skipped_entries: Arc::new(AtomicU64::new(0)),
         })
     }
 
-    /// Get the number of articles.
-    pub fn article_count(&self) -> usize {
-        self.article_list.len()
+    /// Creates another handle to the same archive.
+    ///
+    /// The handles share the memory mapping and the cluster cache, so opening an archive once
+    /// and cloning it is much cheaper than opening it multiple times.
+    pub fn try_clone(&self) -> Zim {
+        Zim {
+            header: self.header.clone(),
+            master_view: self.master_view.clone(),
+            file_path: self.file_path.clone(),
+            mime_table: self.mime_table.clone(),
+            url_list: self.url_list.clone(),
+            article_list: self.article_list.clone(),
+            cluster_list: self.cluster_list.clone(),
+            checksum: self.checksum,
+            cache: self.cache.clone(),
+            blob_index: self.blob_index.clone(),
+            target_counts: self.target_counts.clone(),
+            settings: self.settings,
+            skipped_entries: self.skipped_entries.clone(),
+        }
     }
 
-    /// Computes the checksum, and returns an error if it does not match the one in
-    /// the file.
-    pub fn verify_checksum(&self) -> Result<()> {
-        let checksum_computed = compute_checksum(&self.file_path, self.header.checksum_pos)?;
+    /// Sets how iterating over all entries deals with malformed entries.
+    pub fn set_parse_mode(&mut self, parse_mode: ParseMode) {
+        self.settings.parse_mode = parse_mode;
+    }
 
-        if self.checksum != checksum_computed {
-            return Err(Error::InvalidChecksum);
+    /// Returns `true` if a malformed entry should be skipped.
+    ///
+    /// In lenient mode this counts the entry.
+    pub(crate) fn skip_malformed(&self) -> bool {
+        if self.settings.parse_mode == ParseMode::Strict {
+            return false;
         }
 
-        Ok(())
+        self.skipped_entries.fetch_add(1, Ordering::Relaxed);
+        true
     }
 
-    /// Indexes into the ZIM mime_table.
-    pub fn get_mimetype(&self, id: u16) -> Option<MimeType> {
-        match id {
-            0xffff => Some(MimeType::Redirect),
-            0xfffe => Some(MimeType::LinkTarget),
-            0xfffd => Some(MimeType::DeletedEntry),
-            id => {
-                if (id as usize) < self.mime_table.len() {
-                    Some(MimeType::Type(self.mime_table[id as usize].clone()))
-                } else {
-                    println!("WARNING unknown mimetype idx {}", id);
-                    None
-                }
-            }
-        }
+    /// Get the number of articles.
+    pub fn article_count(&self) -> usize {
+        self.article_list.len()
     }
 
-    /// Iterates over articles, sorted by URL.
+    /// Number of entries that redirect to another entry.
     ///
-    /// For performance reasons, you might want to extract by cluster instead.
-    pub fn iterate_by_urls(&self) -> DirectoryIterator {
-        DirectoryIterator::new(self)
+    /// Redirects and content entries are counted in one pass over all entries, the first time
+    /// either count is requested.
+    pub fn redirect_count(&self) -> Result<u32> {
+        Ok(self.target_counts()?.0)
     }
 
-    /// Returns the `DirectoryEntry` for the article found at the given URL index.
+    /// Number of entries with content, see `redirect_count`.
     ///
-    /// idx must be between 0 and `article_count`
-    pub fn get_by_url_index(&self, idx: u32) -> Result<DirectoryEntry> {
-        let entry_offset = self.url_list[idx as usize] as usize;
-        let (_, dir_view) = self.master_view.split_at(entry_offset);
-
-        DirectoryEntry::new(self, dir_view)
+    /// Together with the redirects, link targets and deleted entries this makes up all entries.
+    pub fn content_count(&self) -> Result<u32> {
+        Ok(self.target_counts()?.1)
     }
 
-    /// Returns the given `Cluster`
-    ///
-    /// idx must be between 0 and `cluster_count`
-    pub fn get_cluster(&self, idx: u32) -> Result<Cluster> {
-        Cluster::new(
-            &self.master_view,
-            &self.cluster_list,
-            idx,
-            self.header.checksum_pos,
-            self.header.version_major,
-        )
+    fn target_counts(&self) -> Result<(u32, u32)> {
+        if let Some(counts) = self.target_counts.get() {
+            return Ok(*counts);
+        }
+
+        let (mut redirects, mut content) = (0, 0);
+        for idx in 0..self.header.article_count {
+            match self.get_by_url_index(idx)?.target {
+                Some(Target::Redirect(_)) => redirects += 1,
+                Some(Target::Cluster(_, _)) => content += 1,
+                None => {}
+            }
+        }
+        let _ = self.target_counts.set((redirects, content));
+
+        Ok((redirects, content))
     }
-}
 
-fn is_defined(val: u32) -> Option<u32> {
-    if val == 0xffffffff {
-        None
-    } else {
-        Some(val)
+    /// Get the number of clusters.
+    pub fn cluster_count(&self) -> u32 {
+        self.header.cluster_count
     }
-}
 
-fn parse_header(master_view: &Mmap) -> Result<(ZimHeader, Vec<String>)> {
-    let mut header_cur = Cursor::new(master_view);
+    /// Returns the raw bytes of the whole archive.
+    ///
+    /// Offsets into the slice are file positions, so the positions stored in the header and the
+    /// pointer lists can be used directly.
+    pub fn raw(&self) -> &[u8] {
+        &self.master_view
+    }
 
-    let magic = header_cur.read_u32::<LittleEndian>()?;
+    /// Number of entries in the URL pointer list.
+    pub fn url_pointer_count(&self) -> u32 {
+        self.url_list.len() as u32
+    }
 
-    if magic != ZIM_MAGIC_NUMBER {
-        return Err(Error::InvalidMagicNumber);
+    /// Returns the URL pointer list: the file offset of every directory entry, in URL order.
+    pub fn url_pointers(&self) -> Result<Vec<u64>> {
+        Ok(self.url_list.clone())
     }
 
-    let version_major = header_cur.read_u16::<LittleEndian>()?;
-    if version_major != 5 && version_major != 6 {
-        return Err(Error::InvalidVersion(version_major));
+    /// Number of entries in the title pointer list.
+    pub fn title_pointer_count(&self) -> u32 {
+        self.article_list.len() as u32
     }
 
-    let version_minor = header_cur.read_u16::<LittleEndian>()?;
+    /// Size of the archive file in bytes.
+    pub fn file_size(&self) -> u64 {
+        self.master_view.len() as u64
+    }
 
-    let mut uuid = [0u8; 16];
-    for el in &mut uuid {
-        *el = header_cur.read_u8()?;
+    /// Position of the MD5 checksum, which is also the size of the archive without it.
+    pub fn checksum_pos(&self) -> u64 {
+        self.header.checksum_pos
     }
 
-    let article_count = header_cur.read_u32::<LittleEndian>()?;
-    let cluster_count = header_cur.read_u32::<LittleEndian>()?;
-    let url_ptr_pos = header_cur.read_u64::<LittleEndian>()?;
-    let title_ptr_pos = header_cur.read_u64::<LittleEndian>()?;
-    let cluster_ptr_pos = header_cur.read_u64::<LittleEndian>()?;
-    let mime_list_pos = header_cur.read_u64::<LittleEndian>()?;
+    /// Size in bytes of all clusters as stored in the file, that is the compressed content.
+    pub fn content_size(&self) -> u64 {
+        match self.cluster_list.first() {
+            Some(start) => self.header.checksum_pos.saturating_sub(*start),
+            None => 0,
+        }
+    }
 
-    let main_page = header_cur.read_u32::<LittleEndian>()?;
-    let layout_page = header_cur.read_u32::<LittleEndian>()?;
-    let checksum_pos = header_cur.read_u64::<LittleEndian>()?;
+    /// Sum of the sizes of all clusters after decompression.
+    ///
+    /// This decompresses every cluster, so it is expensive for large archives.
+    pub fn decompressed_size_estimate(&self) -> Result<u64> {
+        let mut size = 0;
+        for cluster in self.clusters() {
+            size += cluster?.decompressed_size()?;
+        }
 
-    if header_cur.position() != 80 {
-        return Err(Error::InvalidHeader);
+        Ok(size)
     }
 
-    let geo_index_pos = if mime_list_pos > 80 {
-        Some(header_cur.read_u64::<LittleEndian>()?)
-    } else {
-        None
-    };
-
-    // the mime table is always directly after the 80-byte header, so we'll keep
-    // using our header cursor
-    let mime_table = {
-        let mut mime_table = Vec::new();
-        loop {
-            let mut mime_buf = Vec::new();
-            if let Ok(size) = header_cur.read_until(0, &mut mime_buf) {
-                if size <= 1 {
-                    break;
+    /// Sum of the sizes of all blobs of the content namespace, see `content_namespace`.
+    ///
+    /// Metadata, layout resources and redirects are not counted, and blobs shared by several
+    /// entries are counted once for each of them. Every cluster holding content is decompressed,
+    /// so this is expensive for large archives.
+    pub fn total_content_bytes(&self) -> Result<u64> {
+        let mut total = 0;
+        let mut current: Option<(u32, Cluster)> = None;
+        for idx in self.namespace_range(self.content_namespace())? {
+            let (cluster_idx, blob) = match self.get_by_url_index(idx)?.target {
+                Some(Target::Cluster(cluster, blob)) => (cluster, blob),
+                _ => continue,
+            };
+            let cluster = match current {
+                Some((idx, ref cluster)) if idx == cluster_idx => cluster,
+                _ => {
+                    let cluster = self.get_cluster(cluster_idx)?;
+                    cluster.decompress()?;
+                    &current.insert((cluster_idx, cluster)).1
                 }
-                mime_buf.truncate(size - 1);
-                mime_table.push(String::from_utf8(mime_buf)?);
-            }
+            };
+            total += cluster.get_blob_size(blob).ok_or(Error::OutOfBounds)? as u64;
         }
-        mime_table
-    };
-
-    Ok((
-        ZimHeader {
-            version_major,
-            version_minor,
-            uuid: Uuid::new(uuid),
-            article_count,
-            cluster_count,
-            url_ptr_pos,
-            title_ptr_pos,
-            cluster_ptr_pos,
-            mime_list_pos,
-            main_page: is_defined(main_page),
-            layout_page: is_defined(layout_page),
-            checksum_pos,
-            geo_index_pos,
-        },
-        mime_table,
-    ))
-}
 
-/// Parses the URL Pointer List.
-/// See https://wiki.openzim.org/wiki/ZIM_file_format#URL_Pointer_List_.28urlPtrPos.29
-fn parse_url_list(master_view: &Mmap, ptr_pos: u64, count: u32) -> Result<Vec<u64>> {
-    let start = usize::try_from(ptr_pos)?;
-    let end = start + usize::try_from(count)? * 8;
-    let list_view = master_view.get(start..end).ok_or(Error::OutOfBounds)?;
-    let mut cur = Cursor::new(list_view);
+        Ok(total)
+    }
 
-    let mut out: Vec<u64> = Vec::new();
-    for _ in 0..count {
-        out.push(cur.read_u64::<LittleEndian>()?);
+    /// Returns the raw bytes of the header, as stored in the file.
+    pub fn header_bytes(&self) -> &[u8] {
+        &self.master_view[..self.header.size()]
     }
 
-    Ok(out)
-}
+    /// Returns the MD5 checksum stored in the file.
+    pub fn checksum(&self) -> &[u8; 16] {
+        self.checksum
+            .as_slice()
+            .try_into()
+            .expect("MD5 checksums are 16 bytes")
+    }
 
-fn parse_article_list(master_view: &Mmap, ptr_pos: u64, count: u32) -> Result<Vec<u32>> {
-    let start = usize::try_from(ptr_pos)?;
-    let end = start + usize::try_from(count)? * 4;
+    /// Compares the stored checksum against `expected`, e.g. from a mirror's manifest.
+    ///
+    /// Unlike `verify_checksum` this doesn't read the file, so it says nothing about whether the
+    /// content matches the stored checksum.
+    pub fn checksum_matches(&self, expected: &[u8; 16]) -> bool {
+        self.checksum() == expected
+    }
 
-    let list_view = master_view.get(start..end).ok_or(Error::OutOfBounds)?;
+    /// The digest used for the checksum of this archive.
+    pub fn checksum_algorithm(&self) -> ChecksumAlgorithm {
+        ChecksumAlgorithm::for_version(self.header.version_major)
+    }
 
-    let mut cur = Cursor::new(list_view);
-    let mut out: Vec<u32> = Vec::new();
+    /// Computes the checksum, and returns an error if it does not match the one in
+    /// the file.
+    pub fn verify_checksum(&self) -> Result<()> {
+        if self.checksum() != &self.compute_checksum()? {
+            return Err(Error::InvalidChecksum);
+        }
 
-    for _ in 0..count {
-        out.push(cur.read_u32::<LittleEndian>()?);
+        Ok(())
     }
 
-    Ok(out)
-}
+    /// Computes the checksum of the archive, whether or not it matches the stored one.
+    ///
+    /// This reads everything in front of the checksum, i.e. the whole file. Returns
+    /// `Error::OutOfBounds` if the checksum position lies outside the file.
+    pub fn compute_checksum(&self) -> Result<[u8; 16]> {
+        let data = usize::try_from(self.header.checksum_pos)
+            .ok()
+            .and_then(|end| self.master_view.get(..end))
+            .ok_or(Error::OutOfBounds)?;
 
-fn parse_cluster_list(master_view: &Mmap, ptr_pos: u64, count: u32) -> Result<Vec<u64>> {
-    let start = usize::try_from(ptr_pos)?;
-    let end = start + usize::try_from(count)? * 8;
-    let cluster_list_view = master_view.get(start..end).ok_or(Error::OutOfBounds)?;
+        Ok(digest(data, self.checksum_algorithm()).into())
+    }
 
-    let mut cluster_cur = Cursor::new(cluster_list_view);
-    let mut out: Vec<u64> = Vec::new();
+    /// Runs `verify_checksum` on a new thread, and calls `on_done` with its result.
+    ///
+    /// The thread works on a handle created with `try_clone`, so this `Zim` stays usable.
+    pub fn verify_checksum_in_background<F>(&self, on_done: F) -> JoinHandle<()>
+    where
+        F: FnOnce(Result<()>) + Send + 'static,
+    {
+        let zim = self.try_clone();
+        std::thread::spawn(move || on_done(zim.verify_checksum()))
+    }
+
+    /// Checks the structure of the whole archive, stopping at the first problem found.
+    ///
+    /// This verifies the magic number and version, that all pointer lists lie within the file,
+    /// that the pointer lists are consistent with the entry count, that the cluster offsets are
+    /// increasing and within bounds, that no URL is used twice, and finally the checksum.
+    pub fn validate(&self) -> Result<()> {
+        self.validate_with(Validations::ALL)
+    }
+
+    /// Runs the given checks of `validate`, stopping at the first problem found.
+    ///
+    /// Opening an archive already rejects a wrong magic number or version, but both are checked
+    /// again from the file and header as they are now.
+    pub fn validate_with(&self, checks: Validations) -> Result<()> {
+        fn invalid(msg: String) -> Result<()> {
+            Err(Error::InvalidStructure(msg))
+        }
+
+        let view: &[u8] = &self.master_view;
+        if checks.contains(Validations::MAGIC) && !view.starts_with(&ZIM_MAGIC_NUMBER.to_le_bytes())
+        {
+            return Err(Error::InvalidMagicNumber);
+        }
+        if checks.contains(Validations::VERSION) && !matches!(self.header.version_major, 5 | 6) {
+            return Err(Error::InvalidVersion(self.header.version_major));
+        }
+
+        let header = &self.header;
+        let end = header.checksum_pos;
+        if checks.contains(Validations::POINTER_BOUNDS) {
+            self.validate_pointers()?;
+        }
+        if checks.contains(Validations::CLUSTER_MONOTONIC) {
+            if self.cluster_list.len() as u64 != header.cluster_count as u64 {
+                return invalid(format!(
+                    "{} cluster pointers for {} clusters",
+                    self.cluster_list.len(),
+                    header.cluster_count
+                ));
+            }
+            // clusters are stored in order and aren't empty
+            let mut min = header.size() as u64;
+            for (idx, offset) in self.cluster_list.iter().enumerate() {
+                if *offset < min || *offset >= end {
+                    return invalid(format!("cluster {} at invalid offset {}", idx, offset));
+                }
+                min = offset + 1;
+            }
+        }
+
+        if checks.contains(Validations::UNIQUE_URLS) {
+            if let Some((ns, url)) = self.find_duplicate_urls()?.first() {
+                return invalid(format!("duplicate entry {}/{}", ns, url));
+            }
+        }
+
+        if checks.contains(Validations::CHECKSUM) {
+            self.verify_checksum()?;
+        }
+
+        Ok(())
+    }
+
+    /// Returns every `(namespace, url)` that is used by more than one entry.
+    ///
+    /// Such archives break lookups with `get_by_url`. Entries are sorted by URL, so duplicates
+    /// are next to each other and a single scan over all entries finds them.
+    pub fn find_duplicate_urls(&self) -> Result<Vec<(char, String)>> {
+        let mut duplicates = Vec::new();
+        let mut previous: Option<DirectoryEntry> = None;
+        let mut in_run = false;
+        for idx in 0..self.url_list.len() as u32 {
+            let entry = self.get_by_url_index(idx)?;
+            let same = previous.as_ref().is_some_and(|prev| {
+                prev.namespace == entry.namespace && prev.url_bytes() == entry.url_bytes()
+            });
+            // report every URL once, however often it is repeated
+            if same && !in_run {
+                duplicates.push((entry.namespace as u8 as char, entry.url.clone()));
+            }
+            in_run = same;
+            previous = Some(entry);
+        }
+
+        Ok(duplicates)
+    }
+
+    /// The `POINTER_BOUNDS` part of `validate_with`.
+    fn validate_pointers(&self) -> Result<()> {
+        fn invalid(msg: String) -> Result<()> {
+            Err(Error::InvalidStructure(msg))
+        }
+
+        let header = &self.header;
+        let end = header.checksum_pos;
+        let view: &[u8] = &self.master_view;
+        if end.checked_add(16) != Some(view.len() as u64) {
+            return invalid(format!(
+                "checksum at {} isn't 16 bytes before the end of the file",
+                end
+            ));
+        }
+        if header.mime_list_pos < header.size() as u64 || header.mime_list_pos >= end {
+            return invalid(format!("MIME type list at {}", header.mime_list_pos));
+        }
+
+        let articles = header.article_count as u64;
+        let lists = [
+            ("URL", header.url_ptr_pos, articles * 8),
+            ("title", header.title_ptr_pos, articles * 4),
+            (
+                "cluster",
+                header.cluster_ptr_pos,
+                header.cluster_count as u64 * 8,
+            ),
+        ];
+        for (name, pos, len) in lists {
+            if pos < header.size() as u64 || pos.saturating_add(len) > end {
+                return invalid(format!("{} pointer list at {} exceeds the file", name, pos));
+            }
+        }
+
+        let (urls, titles) = (self.url_pointer_count(), self.title_pointer_count());
+        if urls as u64 != articles || titles as u64 != articles {
+            return invalid(format!(
+                "{} URL and {} title pointers for {} entries",
+                urls, titles, articles
+            ));
+        }
+        if let Some(idx) = self.url_list.iter().position(|ptr| *ptr >= end) {
+            return invalid(format!("URL pointer {} exceeds the file", idx));
+        }
+        if let Some(idx) = self.article_list.iter().position(|i| *i as u64 >= articles) {
+            return invalid(format!("title pointer {} is not a valid entry", idx));
+        }
+        for (name, page) in [("main", header.main_page), ("layout", header.layout_page)] {
+            if matches!(page, Some(page) if page as u64 >= articles) {
+                return invalid(format!("{} page is not a valid entry", name));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Indexes into the ZIM mime_table.
+    pub fn get_mimetype(&self, id: u16) -> Option<MimeType> {
+        match MimeType::from_raw(id, &self.mime_table) {
+            Ok(mime_type) => Some(mime_type),
+            Err(_) => {
+                println!("WARNING unknown mimetype idx {}", id);
+                None
+            }
+        }
+    }
+
+    /// Returns the list of mimetypes used in this archive.
+    pub fn mime_types(&self) -> &[String] {
+        &self.mime_table
+    }
+
+    /// Returns the range of bytes the MIME type list occupies in the file.
+    ///
+    /// The list is a sequence of null-terminated strings, ending with an empty string.
+    pub fn mime_list_range(&self) -> Range<u64> {
+        let start = self.header.mime_list_pos;
+        let len: u64 = self.mime_table.iter().map(|m| m.len() as u64 + 1).sum();
+
+        start..start + len + 1
+    }
+
+    /// Returns the raw bytes of the MIME type list, including the terminators.
+    pub fn mime_list_raw(&self) -> &[u8] {
+        let range = self.mime_list_range();
+
+        &self.master_view[range.start as usize..range.end as usize]
+    }
+
+    /// Returns the index of the given mimetype in the mime_table, if present.
+    pub fn mime_index(&self, mime: &str) -> Option<u16> {
+        self.mime_table
+            .iter()
+            .position(|m| m == mime)
+            .and_then(|idx| u16::try_from(idx).ok())
+    }
+
+    /// Returns a handle for accessing entries by URL index.
+    pub fn entries(&self) -> Entries<'_> {
+        Entries::new(self)
+    }
+
+    /// Iterates over articles, sorted by URL.
+    ///
+    /// Entries are yielded in the order of the URL pointer list, which the format requires to be
+    /// sorted by namespace and then URL, compared byte-wise. This is the order `get_by_url`
+    /// relies on for its binary search.
+    ///
+    /// For performance reasons, you might want to extract by cluster instead.
+    pub fn iterate_by_urls(&self) -> DirectoryIterator<'_> {
+        DirectoryIterator::new(self)
+    }
+
+    /// Iterates over articles, sorted by URL, replacing invalid UTF-8 in URLs and titles.
+    ///
+    /// Unlike `iterate_by_urls`, a single entry with a broken URL doesn't end the iteration.
+    pub fn iterate_by_urls_lenient(&self) -> DirectoryIterator<'_> {
+        DirectoryIterator::new_lenient(self)
+    }
+
+    /// Applies `f` to every entry in parallel, returning the results in URL order.
+    ///
+    /// Returns the error of an entry that fails to parse, or skips it in `ParseMode::Lenient`.
+    /// The archive is shared between the worker threads, which is possible as a `Zim` is
+    /// `Sync`.
+    #[cfg(feature = "rayon")]
+    pub fn par_map_entries<T, F>(&self, f: F) -> Result<Vec<T>>
+    where
+        T: Send,
+        F: Fn(DirectoryEntry) -> T + Send + Sync,
+    {
+        (0..self.header.article_count)
+            .into_par_iter()
+            .filter_map(|idx| match self.get_by_url_index(idx) {
+                Ok(entry) => Some(Ok(f(entry))),
+                Err(_) if self.skip_malformed() => None,
+                Err(err) => Some(Err(err)),
+            })
+            .collect()
+    }
+
+    /// Returns the `DirectoryEntry` for the article found at the given URL index.
+    ///
+    /// idx must be below `url_pointer_count`, which normally equals `article_count`. Larger
+    /// indices, and pointers past the end of the file, return `Error::OutOfBounds`.
+    pub fn get_by_url_index(&self, idx: u32) -> Result<DirectoryEntry> {
+        let entry_offset = *self.url_list.get(idx as usize).ok_or(Error::OutOfBounds)?;
+        let dir_view = usize::try_from(entry_offset)
+            .ok()
+            .and_then(|offset| self.master_view.get(offset..))
+            .ok_or(Error::OutOfBounds)?;
+
+        DirectoryEntry::new(self, dir_view)
+    }
+
+    /// Returns the target of the entry at the given URL index, like
+    /// `get_by_url_index(idx)?.target`.
+    ///
+    /// Only the fixed size start of the entry is read, the URL and title aren't parsed, which
+    /// makes this much cheaper when going over all entries.
+    pub fn target_at(&self, idx: u32) -> Result<Option<Target>> {
+        if !matches!(self.header.version_major, 5 | 6) {
+            return Err(Error::InvalidVersion(self.header.version_major));
+        }
+        let entry_offset = *self.url_list.get(idx as usize).ok_or(Error::OutOfBounds)?;
+        let view = usize::try_from(entry_offset)
+            .ok()
+            .and_then(|offset| self.master_view.get(offset..))
+            .ok_or(Error::OutOfBounds)?;
+
+        let mut cur = Cursor::new(view);
+        let mime_type = MimeType::from_raw(cur.read_u16::<LittleEndian>()?, &self.mime_table)?;
+        // skip the parameter length, namespace and revision
+        cur.set_position(8);
+
+        Ok(match mime_type {
+            MimeType::Redirect => Some(Target::Redirect(cur.read_u32::<LittleEndian>()?)),
+            MimeType::LinkTarget | MimeType::DeletedEntry => None,
+            MimeType::Type(_) => {
+                let cluster = cur.read_u32::<LittleEndian>()?;
+                let blob = cur.read_u32::<LittleEndian>()?;
+                Some(Target::Cluster(cluster, blob))
+            }
+        })
+    }
+
+    /// Returns up to `count` entries in URL order, starting at URL index `start`.
+    ///
+    /// Meant for paging through the archive: fewer entries are returned at the end, and none if
+    /// `start` equals `article_count`. A `start` past that is an `Error::OutOfBounds`.
+    pub fn entries_range(&self, start: u32, count: u32) -> Result<Vec<DirectoryEntry>> {
+        let len = self.url_list.len() as u32;
+        if start > len {
+            return Err(Error::OutOfBounds);
+        }
+        let end = start.saturating_add(count).min(len);
+
+        (start..end).map(|idx| self.get_by_url_index(idx)).collect()
+    }
+
+    /// Parses the directory entry starting at `byte_offset` in the file.
+    ///
+    /// The URL and title pointer lists store such offsets. This is meant for debugging, e.g.
+    /// to inspect what a suspicious pointer refers to.
+    pub fn entry_at_offset(&self, byte_offset: u64) -> Result<DirectoryEntry> {
+        let view = usize::try_from(byte_offset)
+            .ok()
+            .and_then(|offset| self.master_view.get(offset..))
+            .filter(|view| !view.is_empty())
+            .ok_or(Error::OutOfBounds)?;
+
+        DirectoryEntry::new(self, view)
+    }
+
+    /// Looks up the `DirectoryEntry` with the given namespace and URL.
+    ///
+    /// Entries are sorted by namespace and URL, so this is a binary search.
+    pub fn get_by_url(&self, ns: char, url: &str) -> Result<Option<DirectoryEntry>> {
+        let key = (ns as u32, url.as_bytes());
+        let (mut lo, mut hi) = (0, self.url_list.len());
+        while lo < hi {
+            let mid = lo + (hi - lo) / 2;
+            let entry = self.get_by_url_index(mid as u32)?;
+            match (entry.namespace as u32, entry.url.as_bytes()).cmp(&key) {
+                std::cmp::Ordering::Less => lo = mid + 1,
+                std::cmp::Ordering::Greater => hi = mid,
+                std::cmp::Ordering::Equal => return Ok(Some(entry)),
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Looks up the URL index of the entry with the given namespace and title.
+    ///
+    /// The title pointer list is sorted by namespace and title, where entries without a title
+    /// use their URL instead, so this is a binary search. The result can be passed to
+    /// `get_by_url_index`.
+    pub fn url_index_of_title(&self, ns: char, title: &str) -> Result<Option<u32>> {
+        let key = (ns as u32, title.as_bytes());
+        let (mut lo, mut hi) = (0, self.article_list.len());
+        while lo < hi {
+            let mid = lo + (hi - lo) / 2;
+            let idx = self.article_list[mid];
+            let entry = self.get_by_url_index(idx)?;
+            let entry_title = if entry.title.is_empty() {
+                &entry.url
+            } else {
+                &entry.title
+            };
+            match (entry.namespace as u32, entry_title.as_bytes()).cmp(&key) {
+                std::cmp::Ordering::Less => lo = mid + 1,
+                std::cmp::Ordering::Greater => hi = mid,
+                std::cmp::Ordering::Equal => return Ok(Some(idx)),
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Returns the content of the entry with the given namespace and URL, following redirects.
+    ///
+    /// Returns `None` if there is no such entry, or if it has no content.
+    pub fn content_by_url(&self, ns: char, url: &str) -> Result<Option<Vec<u8>>> {
+        match self.get_by_url(ns, url)? {
+            Some(entry) => self.read_content(&entry),
+            None => Ok(None),
+        }
+    }
+
+    /// Looks up an entry by a percent-encoded URL, as found in HTTP request paths.
+    ///
+    /// `New%20York` finds the entry with URL `New York`. A `%` that doesn't start a valid escape
+    /// is taken literally, as browsers do. Returns `None` if the URL doesn't decode to valid
+    /// UTF-8, as no entry can match it.
+    pub fn get_by_encoded_url(&self, ns: char, encoded: &str) -> Result<Option<DirectoryEntry>> {
+        match percent_decode_str(encoded).decode_utf8() {
+            Ok(url) => self.get_by_url(ns, &url),
+            Err(_) => Ok(None),
+        }
+    }
+
+    /// Returns the MIME type and a reader over the content of the entry with the given
+    /// namespace and URL, following redirects.
+    ///
+    /// Unlike `content_by_url` the content isn't read up front, see `Cluster::blob_reader`.
+    /// Returns `None` if there is no such entry, or if it has no content.
+    pub fn stream_by_url(&self, ns: char, url: &str) -> Result<Option<(MimeType, BlobReader<'_>)>> {
+        match self.resolve_content(ns, url)? {
+            Some((mime_type, cluster, blob)) => {
+                let reader = self.get_cluster(cluster)?.blob_reader(blob)?;
+                Ok(Some((mime_type, reader)))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Returns the MIME type and the bytes in `range` of the content of the entry with the given
+    /// namespace and URL, following redirects, e.g. to answer an HTTP range request.
+    ///
+    /// Returns `None` if there is no such entry, or if it has no content, and
+    /// `Error::OutOfBounds` if the range doesn't lie within the content.
+    pub fn read_range(
+        &self,
+        ns: char,
+        url: &str,
+        range: Range<usize>,
+    ) -> Result<Option<(MimeType, Vec<u8>)>> {
+        match self.resolve_content(ns, url)? {
+            Some((mime_type, cluster, blob)) => {
+                let cluster = self.get_cluster(cluster)?;
+                let blob = cluster.get_blob(blob)?;
+                Ok(Some((mime_type, blob.slice(range)?.to_vec())))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Looks up the entry with the given namespace and URL and follows redirects, returning the
+    /// MIME type and the cluster and blob of its content.
+    ///
+    /// Returns `None` if there is no such entry, or if it has no content.
+    fn resolve_content(&self, ns: char, url: &str) -> Result<Option<(MimeType, u32, u32)>> {
+        let entry = match self.get_by_url(ns, url)? {
+            Some(entry) => self.resolve(entry)?,
+            None => None,
+        };
+
+        Ok(match entry {
+            Some(DirectoryEntry {
+                mime_type,
+                target: Some(Target::Cluster(cluster, blob)),
+                ..
+            }) => Some((mime_type, cluster, blob)),
+            _ => None,
+        })
+    }
+
+    /// Returns up to `limit` entries whose URL contains `substring`, in URL order.
+    ///
+    /// Unlike `get_by_url`, this scans every entry and takes time linear in the size of the
+    /// archive. It is meant as a fallback, e.g. to suggest entries when an exact lookup fails.
+    pub fn search_urls(&self, substring: &str, limit: usize) -> Vec<DirectoryEntry> {
+        self.iterate_by_urls()
+            .filter(|entry| entry.url.contains(substring))
+            .take(limit)
+            .collect()
+    }
+
+    /// Returns the range of URL indices of the entries in the given namespace.
+    pub fn namespace_range(&self, ns: char) -> Result<Range<u32>> {
+        let start = self.namespace_lower_bound(ns as u32)?;
+        let end = self.namespace_lower_bound(ns as u32 + 1)?;
+
+        Ok(start..end)
+    }
+
+    /// Returns the number of entries in the given namespace, using two binary searches.
+    pub fn namespace_entry_count(&self, ns: char) -> Result<u32> {
+        Ok(self.namespace_range(ns)?.len() as u32)
+    }
+
+    /// Returns the namespaces that have entries, in sorted order.
+    ///
+    /// Entries are sorted by namespace, so this only looks up where each namespace ends with a
+    /// binary search, instead of reading all entries.
+    pub fn namespaces(&self) -> Result<Vec<char>> {
+        let mut namespaces = Vec::new();
+        let mut idx = 0;
+        while (idx as usize) < self.url_list.len() {
+            let ns = self.get_by_url_index(idx)?.namespace as u32;
+            namespaces.push(ns as u8 as char);
+            idx = self.namespace_lower_bound(ns + 1)?;
+        }
+
+        Ok(namespaces)
+    }
+
+    /// Returns the first URL index whose namespace is not smaller than `ns`.
+    fn namespace_lower_bound(&self, ns: u32) -> Result<u32> {
+        let (mut lo, mut hi) = (0, self.url_list.len() as u32);
+        while lo < hi {
+            let mid = lo + (hi - lo) / 2;
+            if (self.get_by_url_index(mid)?.namespace as u32) < ns {
+                lo = mid + 1;
+            } else {
+                hi = mid;
+            }
+        }
+
+        Ok(lo)
+    }
+
+    /// The namespace articles are stored in.
+    ///
+    /// Since version 6.1 all content lives in the `C` namespace, older archives use `A`.
+    pub fn content_namespace(&self) -> char {
+        if (self.header.version_major, self.header.version_minor) >= (6, 1) {
+            'C'
+        } else {
+            'A'
+        }
+    }
+
+    /// Iterates over the user facing articles, sorted by URL.
+    ///
+    /// Only HTML entries of the content namespace are returned, skipping redirects, metadata
+    /// and assets like images or stylesheets. Like `iterate_by_urls`, iteration stops at a
+    /// malformed entry, or skips it in `ParseMode::Lenient`.
+    pub fn articles(&self) -> Result<impl Iterator<Item = DirectoryEntry> + '_> {
+        let range = self.namespace_range(self.content_namespace())?;
+        let html = self
+            .mime_index("text/html")
+            .and_then(|idx| self.get_mimetype(idx));
+
+        Ok(range
+            .map(move |idx| self.get_by_url_index(idx))
+            .filter(move |entry| entry.is_ok() || !self.skip_malformed())
+            .map_while(|entry| entry.ok())
+            .filter(move |entry| html.as_ref() == Some(&entry.mime_type)))
+    }
+
+    /// Iterates over the display titles of the articles returned by `articles`, sorted by title.
+    ///
+    /// Entries without a title use their URL. Only the fields needed to pick the articles and
+    /// their titles are read, no `DirectoryEntry` is built. Titles that aren't valid UTF-8 are
+    /// skipped.
+    pub fn titles(&self) -> impl Iterator<Item = String> + '_ {
+        let ns = self.content_namespace() as u8;
+        let html = self.mime_index("text/html");
+
+        self.article_list.iter().filter_map(move |idx| {
+            let offset = usize::try_from(*self.url_list.get(*idx as usize)?).ok()?;
+            read_article_title(self.master_view.get(offset..)?, ns, html?)
+        })
+    }
+
+    /// Iterates over the entries of the `-` namespace, sorted by URL.
+    ///
+    /// Older archives store the stylesheets and scripts used by their pages there, next to the
+    /// layout page. Newer archives keep them in the content namespace and have none. Like
+    /// `iterate_by_urls`, iteration stops at a malformed entry, or skips it in
+    /// `ParseMode::Lenient`.
+    pub fn layout_resources(&self) -> Result<impl Iterator<Item = DirectoryEntry> + '_> {
+        let range = self.namespace_range('-')?;
+
+        Ok(range
+            .map(move |idx| self.get_by_url_index(idx))
+            .filter(move |entry| entry.is_ok() || !self.skip_malformed())
+            .map_while(|entry| entry.ok()))
+    }
+
+    /// Counts the entries with content per MIME type.
+    ///
+    /// Uses the `M/Counter` metadata entry if the archive has one, and otherwise scans all
+    /// entries. Writers fill the counter with every entry that has content, except metadata and
+    /// search indices, so the scan skips the `M` and `X` namespaces to give the same result.
+    /// Redirects are never counted. A counter that isn't valid UTF-8 or can't be parsed is
+    /// treated as absent.
+    pub fn mime_histogram(&self) -> Result<(BTreeMap<MimeType, u64>, MimeHistogramSource)> {
+        let counter = match self.read_metadata("Counter") {
+            Ok(counter) => counter,
+            Err(Error::Parsing(_)) => None,
+            Err(err) => return Err(err),
+        };
+        if let Some(histogram) = counter.as_deref().and_then(parse_counter) {
+            return Ok((histogram, MimeHistogramSource::Counter));
+        }
+
+        let mut histogram = BTreeMap::new();
+        for idx in 0..self.header.article_count {
+            let entry = self.get_by_url_index(idx)?;
+            if matches!(
+                entry.namespace,
+                Namespace::Metadata | Namespace::FulltextIndex
+            ) {
+                continue;
+            }
+            if let MimeType::Type(_) = entry.mime_type {
+                *histogram.entry(entry.mime_type).or_insert(0) += 1;
+            }
+        }
+
+        Ok((histogram, MimeHistogramSource::Scan))
+    }
+
+    /// Returns all metadata entries of the `M` namespace, such as `Title` or `Language`.
+    ///
+    /// Values that aren't valid UTF-8, like the `Illustration_*` images, are skipped.
+    pub fn all_metadata(&self) -> Result<BTreeMap<String, String>> {
+        let mut metadata = BTreeMap::new();
+        for idx in self.namespace_range('M')? {
+            let entry = self.get_by_url_index(idx)?;
+            if let Some(data) = self.read_content(&entry)? {
+                if let Ok(value) = String::from_utf8(data) {
+                    metadata.insert(entry.url, value);
+                }
+            }
+        }
+
+        Ok(metadata)
+    }
+
+    /// Returns the date the archive was created, from the `M/Date` metadata.
+    ///
+    /// Returns `None` if there is no such entry or it isn't a valid `YYYY-MM-DD` date.
+    /// Surrounding whitespace is ignored.
+    #[cfg(feature = "time")]
+    pub fn date(&self) -> Result<Option<time::Date>> {
+        let format = time::macros::format_description!("[year]-[month]-[day]");
+        Ok(match self.read_metadata("Date") {
+            Ok(date) => date.and_then(|date| time::Date::parse(date.trim(), format).ok()),
+            Err(Error::Parsing(_)) => None,
+            Err(err) => return Err(err),
+        })
+    }
+
+    /// Reads the metadata entry `M/<key>` as a string.
+    fn read_metadata(&self, key: &str) -> Result<Option<String>> {
+        match self.get_by_url('M', key)? {
+            Some(entry) => match self.read_content(&entry)? {
+                Some(data) => Ok(Some(String::from_utf8(data)?)),
+                None => Ok(None),
+            },
+            None => Ok(None),
+        }
+    }
+
+    /// Returns the icon of this archive with the given edge length in pixels.
+    ///
+    /// Looks for the `M/Illustration_{size}x{size}@1` metadata entry first, and falls back to
+    /// `-/favicon`, `I/favicon` and `I/favicon.png` as used by older archives.
+    pub fn illustration(&self, size: u32) -> Result<Option<Vec<u8>>> {
+        for (ns, url) in icon_candidates(size) {
+            if let Some(entry) = self.get_by_url(ns, &url)? {
+                if let Some(data) = self.read_content(&entry)? {
+                    return Ok(Some(data));
+                }
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Returns the icon of this archive, together with its MIME type.
+    ///
+    /// Tries the same entries as `illustration(48)`, then the icon the main page links to with
+    /// `<link rel="icon" href="...">`.
+    pub fn icon(&self) -> Result<Option<(MimeType, Vec<u8>)>> {
+        for (ns, url) in icon_candidates(48) {
+            if let Some(entry) = self.get_by_url(ns, &url)? {
+                if let Some(icon) = self.read_with_mime_type(entry)? {
+                    return Ok(Some(icon));
+                }
+            }
+        }
+
+        let main_page = match self.header.main_page {
+            Some(idx) if idx < self.header.article_count => self.get_by_url_index(idx)?,
+            _ => return Ok(None),
+        };
+        let html = match self.read_content(&main_page)? {
+            Some(html) => String::from_utf8_lossy(&html).into_owned(),
+            None => return Ok(None),
+        };
+        let link = find_icon_link(&html)
+            .and_then(|href| resolve_link(main_page.namespace as u8 as char, &main_page.url, href));
+        if let Some((ns, url)) = link {
+            if let Some(entry) = self.get_by_url(ns, &url)? {
+                return self.read_with_mime_type(entry);
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Reads the content of an entry and its MIME type, following redirects.
+    fn read_with_mime_type(&self, entry: DirectoryEntry) -> Result<Option<(MimeType, Vec<u8>)>> {
+        let entry = match self.resolve(entry)? {
+            Some(entry) => entry,
+            None => return Ok(None),
+        };
+
+        Ok(self
+            .read_content(&entry)?
+            .map(|data| (entry.mime_type, data)))
+    }
+
+    /// Picks a random article, as returned by `articles`.
+    ///
+    /// Every article is equally likely. Redirects aren't articles, so the returned entry always
+    /// has content. This reads all articles once but keeps only the chosen one. Returns `None`
+    /// if the archive has no articles.
+    #[cfg(feature = "rand")]
+    pub fn random_article<R: Rng + ?Sized>(&self, rng: &mut R) -> Result<Option<DirectoryEntry>> {
+        Ok(self.articles()?.choose(rng))
+    }
+
+    /// Returns the entry a redirect points to, without following further redirects.
+    ///
+    /// Returns `None` if `entry` isn't a redirect.
+    pub fn redirect_target(&self, entry: &DirectoryEntry) -> Result<Option<DirectoryEntry>> {
+        match entry.target {
+            Some(Target::Redirect(idx)) => self.get_by_url_index(idx).map(Some),
+            _ => Ok(None),
+        }
+    }
+
+    /// Follows redirects until reaching an entry that isn't one.
+    ///
+    /// Returns `None` for redirect loops.
+    fn resolve(&self, mut entry: DirectoryEntry) -> Result<Option<DirectoryEntry>> {
+        for _ in 0..=self.header.article_count {
+            match entry.target {
+                Some(Target::Redirect(idx)) => entry = self.get_by_url_index(idx)?,
+                _ => return Ok(Some(entry)),
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Reads the content of an entry, following redirects.
+    fn read_content(&self, entry: &DirectoryEntry) -> Result<Option<Vec<u8>>> {
+        let target = match entry.target {
+            Some(Target::Redirect(idx)) => self
+                .resolve(self.get_by_url_index(idx)?)?
+                .and_then(|entry| entry.target),
+            target => target,
+        };
+
+        match target {
+            Some(Target::Cluster(cluster, blob)) => {
+                let cluster = self.get_cluster(cluster)?;
+                let blob = cluster.get_blob(blob)?;
+                Ok(Some(blob.to_vec()))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// Returns all entries with content, ordered by cluster and blob instead of by URL.
+    ///
+    /// Processing entries in this order touches every cluster exactly once, in the order they
+    /// are stored in the file. Redirects, link targets and deleted entries are skipped, as they
+    /// have no content of their own. All entries are read up front to sort them.
+    pub fn entries_by_cluster(&self) -> Result<impl Iterator<Item = DirectoryEntry>> {
+        let mut entries = Vec::new();
+        for idx in 0..self.header.article_count {
+            let entry = match self.get_by_url_index(idx) {
+                Ok(entry) => entry,
+                Err(_) if self.skip_malformed() => continue,
+                Err(err) => return Err(err),
+            };
+            if let Some(Target::Cluster(cluster, blob)) = entry.target {
+                entries.push((cluster, blob, entry));
+            }
+        }
+        entries.sort_by_key(|(cluster, blob, _)| (*cluster, *blob));
+
+        Ok(entries.into_iter().map(|(_, _, entry)| entry))
+    }
+
+    /// Returns the entries of the given namespace together with their content, e.g. to extract
+    /// all images.
+    ///
+    /// Entries with content come first, ordered by cluster and blob so each cluster is
+    /// decompressed once. Redirects, link targets and deleted entries follow in URL order with
+    /// empty content, so callers can still recreate them. The entries are read up front; if one
+    /// fails to parse, the only item is the error, unless `ParseMode::Lenient` skips it.
+    pub fn blobs_in_namespace<'a>(
+        &'a self,
+        ns: char,
+    ) -> impl Iterator<Item = Result<(DirectoryEntry, Vec<u8>)>> + 'a {
+        let mut entries = Vec::new();
+        let mut error = None;
+        match self.namespace_range(ns) {
+            Ok(range) => {
+                for idx in range {
+                    match self.get_by_url_index(idx) {
+                        Ok(entry) => entries.push(entry),
+                        Err(_) if self.skip_malformed() => {}
+                        Err(err) => {
+                            entries.clear();
+                            error = Some(err);
+                            break;
+                        }
+                    }
+                }
+            }
+            Err(err) => error = Some(err),
+        }
+        entries.sort_by_key(|entry| match entry.target {
+            Some(Target::Cluster(cluster, blob)) => (false, cluster, blob),
+            _ => (true, 0, 0),
+        });
+
+        let mut current: Option<(u32, Cluster<'a>)> = None;
+        let blobs = entries.into_iter().map(move |entry| {
+            let (cluster, blob) = match entry.target {
+                Some(Target::Cluster(cluster, blob)) => (cluster, blob),
+                _ => return Ok((entry, Vec::new())),
+            };
+            if current.as_ref().map(|(idx, _)| *idx) != Some(cluster) {
+                // decompressed once up front, the readers below share the data
+                let next = self.get_cluster(cluster)?;
+                next.decompress()?;
+                current = Some((cluster, next));
+            }
+            let mut data = Vec::new();
+            if let Some((_, cluster)) = &current {
+                cluster.blob_reader(blob)?.read_to_end(&mut data)?;
+            }
+
+            Ok((entry, data))
+        });
+
+        error.map(Err).into_iter().chain(blobs)
+    }
+
+    /// Returns the entry whose content is blob `blob` of cluster `cluster`.
+    ///
+    /// If several entries share the blob, the first one in URL order is returned. The first call
+    /// reads all entries to build a reverse index, later calls and clones of this `Zim` reuse it.
+    pub fn entry_for_blob(&self, cluster: u32, blob: u32) -> Result<Option<DirectoryEntry>> {
+        if self.blob_index.get().is_none() {
+            let mut index = HashMap::new();
+            for idx in 0..self.header.article_count {
+                if let Some(Target::Cluster(cluster, blob)) = self.get_by_url_index(idx)?.target {
+                    index.entry((cluster, blob)).or_insert(idx);
+                }
+            }
+            // another thread may have been faster, both indices are the same
+            let _ = self.blob_index.set(index);
+        }
+
+        match self
+            .blob_index
+            .get()
+            .and_then(|index| index.get(&(cluster, blob)))
+        {
+            Some(idx) => self.get_by_url_index(*idx).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Maps the SHA-256 digest of every blob to the URL indices of the entries with that content,
+    /// in ascending order.
+    ///
+    /// Entries with identical content end up under the same key, which identifies content that
+    /// can be deduplicated. Every cluster is decompressed and every blob hashed, in parallel if
+    /// the `rayon` feature is enabled.
+    pub fn build_content_index(&self) -> Result<HashMap<[u8; 32], Vec<u32>>> {
+        let mut by_cluster: BTreeMap<u32, Vec<(u32, u32)>> = BTreeMap::new();
+        for idx in 0..self.header.article_count {
+            if let Some(Target::Cluster(cluster, blob)) = self.get_by_url_index(idx)?.target {
+                by_cluster.entry(cluster).or_default().push((blob, idx));
+            }
+        }
+
+        let hash_cluster = |(cluster, entries): (&u32, &Vec<(u32, u32)>)| {
+            let cluster = self.get_cluster(*cluster)?;
+            let reader = cluster.reader()?;
+            entries
+                .iter()
+                .map(|(blob, idx)| Ok((Sha256::digest(reader.get(*blob)?).into(), *idx)))
+                .collect::<Result<Vec<([u8; 32], u32)>>>()
+        };
+        #[cfg(feature = "rayon")]
+        let hashes: Vec<_> = by_cluster
+            .par_iter()
+            .map(hash_cluster)
+            .collect::<Result<_>>()?;
+        #[cfg(not(feature = "rayon"))]
+        let hashes: Vec<_> = by_cluster.iter().map(hash_cluster).collect::<Result<_>>()?;
+
+        let mut index: HashMap<[u8; 32], Vec<u32>> = HashMap::new();
+        for (hash, idx) in hashes.into_iter().flatten() {
+            index.entry(hash).or_default().push(idx);
+        }
+        for indices in index.values_mut() {
+            indices.sort_unstable();
+        }
+
+        Ok(index)
+    }
+
+    /// Writes the content of every entry into a tar archive, at `<namespace>/<url>`.
+    ///
+    /// Entries are written in cluster order, so every cluster is decompressed only once.
+    /// Redirects, link targets and deleted entries have no content of their own and are skipped.
+    /// Every segment of the URL is escaped with `escape_path_segment`, so extracting the tar
+    /// can't write outside the destination.
+    #[cfg(feature = "tar")]
+    pub fn extract_to_tar<W: Write>(&self, writer: W) -> Result<()> {
+        let mut tar = tar::Builder::new(writer);
+        let mut current: Option<(u32, Cluster)> = None;
+        for entry in self.entries_by_cluster()? {
+            let (cluster_idx, blob_idx) = match entry.target {
+                Some(Target::Cluster(cluster, blob)) => (cluster, blob),
+                _ => unreachable!("only entries with content are returned"),
+            };
+            if current.as_ref().map(|(idx, _)| *idx) != Some(cluster_idx) {
+                current = Some((cluster_idx, self.get_cluster(cluster_idx)?));
+            }
+            let (_, cluster) = current.as_ref().unwrap();
+
+            let mut path = (entry.namespace as u8 as char).to_string();
+            // empty segments, e.g. from absolute urls, are skipped
+            for segment in entry.url.split('/').filter(|segment| !segment.is_empty()) {
+                path.push('/');
+                path.push_str(&escape_path_segment(segment));
+            }
+
+            let blob = cluster.get_blob(blob_idx)?;
+            let mut header = tar::Header::new_gnu();
+            header.set_size(blob.len() as u64);
+            header.set_mode(0o644);
+            tar.append_data(&mut header, &path, blob.as_ref())?;
+        }
+        tar.into_inner()?;
+
+        Ok(())
+    }
+
+    /// Returns the cache and decompression counters of this archive and its clones.
+    pub fn stats(&self) -> ZimStats {
+        let cache = self.cache.stats();
+        ZimStats {
+            cache_hits: cache.hits,
+            cache_misses: cache.misses,
+            clusters_decompressed: cache.clusters_decompressed,
+            bytes_decompressed: cache.bytes_decompressed,
+            entries_skipped: self.skipped_entries.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Tells the kernel how the archive is going to be read, e.g. `Sequential` for an extraction
+    /// job and `Random` for a server.
+    ///
+    /// This only affects performance. It applies to the whole mapping, so clones created with
+    /// `try_clone` are affected as well. Does nothing for archives held in memory and on
+    /// platforms without `madvise`.
+    pub fn set_access_pattern(&self, pattern: AccessPattern) -> Result<()> {
+        self.master_view.advise(0..self.master_view.len(), pattern)
+    }
+
+    /// Drops all decompressed cluster data cached by this archive and its clones.
+    ///
+    /// Clusters that are still alive keep the data they already decompressed until they are
+    /// dropped, everything else is decompressed again on the next access.
+    pub fn clear_cache(&self) {
+        self.cache.clear();
+    }
+
+    /// Returns the memory budget in bytes for decompressed clusters, `DEFAULT_CACHE_SIZE` unless
+    /// set with `ZimBuilder::cache_size`.
+    pub fn cache_size(&self) -> usize {
+        self.cache.capacity()
+    }
+
+    /// Returns the given `Cluster`
+    ///
+    /// idx must be between 0 and `cluster_count`
+    pub fn get_cluster(&self, idx: u32) -> Result<Cluster<'_>> {
+        Cluster::with_cache(
+            &self.master_view,
+            &self.cluster_list,
+            idx,
+            self.header.checksum_pos,
+            self.header.version_major,
+            &self.cache,
+        )
+    }
+
+    /// Like `get_cluster`, but returns `None` if `idx` is not below `cluster_count`.
+    pub fn try_get_cluster(&self, idx: u32) -> Result<Option<Cluster<'_>>> {
+        if idx >= self.cluster_count() {
+            return Ok(None);
+        }
+
+        self.get_cluster(idx).map(Some)
+    }
+
+    /// Returns the compression of the given cluster, reading only its info byte.
+    pub fn cluster_compression(&self, idx: u32) -> Result<Compression> {
+        Ok(parse_cluster_info(self.cluster_info_byte(idx)?)?.1)
+    }
+
+    /// Reads the info byte of the given cluster, without creating a `Cluster`.
+    fn cluster_info_byte(&self, idx: u32) -> Result<u8> {
+        self.cluster_list
+            .get(idx as usize)
+            .and_then(|offset| usize::try_from(*offset).ok())
+            .and_then(|offset| self.master_view.get(offset))
+            .copied()
+            .ok_or(Error::OutOfBounds)
+    }
+
+    /// Returns the given cluster as an uncompressed cluster, ready to be written to a new archive.
+    ///
+    /// The result is the info byte, keeping the extended flag, followed by the blob offset table
+    /// and all blobs. Serving blobs from such a cluster needs no decompression.
+    pub fn clone_cluster_uncompressed(&self, idx: u32) -> Result<Vec<u8>> {
+        let (extended, _) = parse_cluster_info(self.cluster_info_byte(idx)?)?;
+        let data = self.get_cluster(idx)?.decompressed()?;
+
+        let mut out = Vec::with_capacity(data.len() + 1);
+        out.push(u8::from(Compression::None) | if extended { 0x10 } else { 0 });
+        out.extend_from_slice(&data);
+
+        Ok(out)
+    }
+
+    /// Returns `true` if this crate can read the whole archive.
+    ///
+    /// Checks the version, and that every cluster uses a supported compression. Only the info
+    /// byte of each cluster is read, so this is cheap compared to decompressing anything.
+    pub fn is_supported(&self) -> bool {
+        matches!(self.header.version_major, 5 | 6)
+            && (0..self.header.cluster_count).all(|idx| {
+                self.cluster_compression(idx)
+                    .is_ok_and(|compression| compression.is_supported())
+            })
+    }
+
+    /// Iterates over all clusters, in the order they are stored.
+    ///
+    /// Clusters are created lazily, and nothing is decompressed until it is accessed.
+    pub fn clusters(&self) -> impl Iterator<Item = Result<Cluster<'_>>> {
+        (0..self.header.cluster_count).map(move |idx| self.get_cluster(idx))
+    }
+
+    /// Returns the `(start, end)` byte range of every cluster in the file, in order.
+    ///
+    /// The last cluster ends where the checksum starts.
+    pub fn cluster_ranges(&self) -> Result<Vec<(u64, u64)>> {
+        let ends = self
+            .cluster_list
+            .iter()
+            .skip(1)
+            .chain(std::iter::once(&self.header.checksum_pos));
+
+        self.cluster_list
+            .iter()
+            .zip(ends)
+            .map(|(start, end)| {
+                if end < start {
+                    return Err(Error::InvalidHeader);
+                }
+                Ok((*start, *end))
+            })
+            .collect()
+    }
+
+    /// Decompresses every cluster, calling `cb` with `(done, total)` after each one.
+    ///
+    /// This warms up the cluster cache, e.g. before serving an archive, so that the first
+    /// requests don't pay for decompression. The cache keeps its memory budget, so for large
+    /// archives earlier clusters get evicted again.
+    pub fn decompress_all(&self, mut cb: impl FnMut(u32, u32)) -> Result<()> {
+        let total = self.header.cluster_count;
+        for idx in 0..total {
+            self.get_cluster(idx)?.decompress()?;
+            cb(idx + 1, total);
+        }
+
+        Ok(())
+    }
+}
+
+/// Parses the `M/Counter` metadata, e.g. `text/html=3;image/png=1`.
+fn parse_counter(counter: &str) -> Option<BTreeMap<MimeType, u64>> {
+    let mut histogram = BTreeMap::new();
+    for part in counter.split(';').filter(|part| !part.is_empty()) {
+        let (mime, count) = part.rsplit_once('=')?;
+        histogram.insert(MimeType::Type(mime.to_string()), count.parse().ok()?);
+    }
+
+    Some(histogram)
+}
+
+fn is_defined(val: u32) -> Option<u32> {
+    if val == 0xffffffff {
+        None
+    } else {
+        Some(val)
+    }
+}
+
+/// The entries `Zim::illustration` and `Zim::icon` look for, in order: the `Illustration`
+/// metadata of the new scheme, then the favicons older archives store in `-` or `I`.
+fn icon_candidates(size: u32) -> [(char, String); 4] {
+    [
+        ('M', format!("Illustration_{}x{}@1", size, size)),
+        ('-', "favicon".to_string()),
+        ('I', "favicon".to_string()),
+        ('I', "favicon.png".to_string()),
+    ]
+}
+
+/// Returns the `href` of the first `<link>` tag whose `rel` contains `icon` and that has one.
+fn find_icon_link(html: &str) -> Option<&str> {
+    let lower = html.to_ascii_lowercase();
+    let mut rest = 0;
+    while let Some(start) = lower[rest..].find("<link").map(|pos| rest + pos) {
+        let end = lower[start..]
+            .find('>')
+            .map_or(lower.len(), |pos| start + pos);
+        let tag = start..end;
+        rest = end;
+
+        let rel = attribute(&lower[tag.clone()], "rel");
+        if rel.is_some_and(|rel| rel.split_whitespace().any(|r| r == "icon")) {
+            // the lowercase copy has the same byte offsets as the original
+            let href = match attribute(&lower[tag.clone()], "href") {
+                Some(href) => href,
+                None => continue,
+            };
+            let offset = href.as_ptr() as usize - lower.as_ptr() as usize;
+            return Some(&html[offset..offset + href.len()]);
+        }
+    }
+
+    None
+}
+
+/// Returns the value of the attribute `name` in the tag `tag`.
+fn attribute<'t>(tag: &'t str, name: &str) -> Option<&'t str> {
+    let mut rest = tag;
+    while let Some(pos) = rest.find(name) {
+        let before = rest[..pos].chars().last();
+        let after = rest[pos + name.len()..].trim_start();
+        rest = &rest[pos + name.len()..];
+        if !before.is_some_and(char::is_whitespace) {
+            continue;
+        }
+        if let Some(value) = after.strip_prefix('=') {
+            let value = value.trim_start();
+            let (quote, value) = match value.chars().next() {
+                Some(q @ ('"' | '\'')) => (Some(q), &value[1..]),
+                _ => (None, value),
+            };
+            let end = match quote {
+                Some(q) => value.find(q),
+                None => value.find(char::is_whitespace),
+            };
+            return Some(&value[..end.unwrap_or(value.len())]);
+        }
+    }
+
+    None
+}
+
+/// Resolves a relative link found in the entry `ns/url` to the namespace and URL it points to.
+fn resolve_link(ns: char, url: &str, href: &str) -> Option<(char, String)> {
+    let href = href.split(['#', '?']).next()?;
+    if href.is_empty() || href.starts_with('/') || href.contains(':') {
+        return None;
+    }
+
+    let base = format!("{}/{}", ns, url);
+    let mut segments: Vec<Cow<'_, str>> = base.split('/').map(Cow::Borrowed).collect();
+    // links are relative to the directory of the entry
+    segments.pop();
+    for segment in href.split('/') {
+        // hrefs are percent-encoded, the URLs of entries aren't
+        let segment = percent_decode_str(segment).decode_utf8().ok()?;
+        match segment.as_ref() {
+            "." | "" => {}
+            ".." => {
+                segments.pop()?;
+            }
+            _ => segments.push(Cow::Owned(segment.into_owned())),
+        }
+    }
+
+    let mut chars = segments.first()?.chars();
+    match (chars.next(), chars.next()) {
+        (Some(ns), None) if segments.len() > 1 => Some((ns, segments[1..].join("/"))),
+        _ => None,
+    }
+}
+
+fn parse_header(master_view: &[u8]) -> Result<(ZimHeader, Vec<String>)> {
+    let mut header_cur = Cursor::new(master_view);
+
+    let magic = header_cur.read_u32::<LittleEndian>()?;
+
+    if magic != ZIM_MAGIC_NUMBER {
+        return Err(Error::InvalidMagicNumber);
+    }
+
+    let version_major = header_cur.read_u16::<LittleEndian>()?;
+    if version_major != 5 && version_major != 6 {
+        return Err(Error::InvalidVersion(version_major));
+    }
+
+    let version_minor = header_cur.read_u16::<LittleEndian>()?;
+
+    let mut uuid = [0u8; 16];
+    for el in &mut uuid {
+        *el = header_cur.read_u8()?;
+    }
+
+    let article_count = header_cur.read_u32::<LittleEndian>()?;
+    let cluster_count = header_cur.read_u32::<LittleEndian>()?;
+    let url_ptr_pos = header_cur.read_u64::<LittleEndian>()?;
+    let title_ptr_pos = header_cur.read_u64::<LittleEndian>()?;
+    let cluster_ptr_pos = header_cur.read_u64::<LittleEndian>()?;
+    let mime_list_pos = header_cur.read_u64::<LittleEndian>()?;
+
+    let main_page = header_cur.read_u32::<LittleEndian>()?;
+    let layout_page = header_cur.read_u32::<LittleEndian>()?;
+    let checksum_pos = header_cur.read_u64::<LittleEndian>()?;
+
+    if header_cur.position() != ZIM_HEADER_SIZE as u64 {
+        return Err(Error::InvalidHeader);
+    }
+
+    let geo_index_pos = if mime_list_pos > ZIM_HEADER_SIZE as u64 {
+        Some(header_cur.read_u64::<LittleEndian>()?)
+    } else {
+        None
+    };
+
+    // the mime table is always directly after the 80-byte header, so we'll keep
+    // using our header cursor
+    let mime_table = {
+        let mut mime_table = Vec::new();
+        loop {
+            let mut mime_buf = Vec::new();
+            if let Ok(size) = header_cur.read_until(0, &mut mime_buf) {
+                if size <= 1 {
+                    break;
+                }
+                mime_buf.truncate(size - 1);
+                mime_table.push(String::from_utf8(mime_buf)?);
+            }
+        }
+        mime_table
+    };
+
+    Ok((
+        ZimHeader {
+            version_major,
+            version_minor,
+            uuid: Uuid::new(uuid),
+            article_count,
+            cluster_count,
+            url_ptr_pos,
+            title_ptr_pos,
+            cluster_ptr_pos,
+            mime_list_pos,
+            main_page: is_defined(main_page),
+            layout_page: is_defined(layout_page),
+            checksum_pos,
+            geo_index_pos,
+        },
+        mime_table,
+    ))
+}
+
+/// Parses the URL Pointer List.
+/// See https://wiki.openzim.org/wiki/ZIM_file_format#URL_Pointer_List_.28urlPtrPos.29
+fn parse_url_list(master_view: &[u8], ptr_pos: u64, count: u32) -> Result<Vec<u64>> {
+    let start = usize::try_from(ptr_pos)?;
+    let end = start
+        .checked_add(usize::try_from(count)? * 8)
+        .ok_or(Error::OutOfBounds)?;
+    let list_view = master_view.get(start..end).ok_or(Error::OutOfBounds)?;
+    let mut cur = Cursor::new(list_view);
+
+    let mut out: Vec<u64> = Vec::new();
+    for _ in 0..count {
+        out.push(cur.read_u64::<LittleEndian>()?);
+    }
+
+    Ok(out)
+}
+
+fn parse_article_list(master_view: &[u8], ptr_pos: u64, count: u32) -> Result<Vec<u32>> {
+    let start = usize::try_from(ptr_pos)?;
+    let end = start
+        .checked_add(usize::try_from(count)? * 4)
+        .ok_or(Error::OutOfBounds)?;
+
+    let list_view = master_view.get(start..end).ok_or(Error::OutOfBounds)?;
+
+    let mut cur = Cursor::new(list_view);
+    let mut out: Vec<u32> = Vec::new();
+
+    for _ in 0..count {
+        out.push(cur.read_u32::<LittleEndian>()?);
+    }
+
+    Ok(out)
+}
+
+fn parse_cluster_list(master_view: &[u8], ptr_pos: u64, count: u32) -> Result<Vec<u64>> {
+    let start = usize::try_from(ptr_pos)?;
+    let end = start
+        .checked_add(usize::try_from(count)? * 8)
+        .ok_or(Error::OutOfBounds)?;
+    let cluster_list_view = master_view.get(start..end).ok_or(Error::OutOfBounds)?;
+
+    let mut cluster_cur = Cursor::new(cluster_list_view);
+    let mut out: Vec<u64> = Vec::new();
     for _ in 0..count {
         out.push(cluster_cur.read_u64::<LittleEndian>()?);
     }
-    Ok(out)
-}
+    Ok(out)
+}
+
+/// Read out the the 16 byte long MD5 checksum.
+fn read_checksum(master_view: &[u8], checksum_pos: u64) -> Result<Checksum> {
+    let checksum_pos = usize::try_from(checksum_pos)?;
+    match master_view.get(checksum_pos..checksum_pos.saturating_add(16)) {
+        Some(raw) => {
+            let mut arr = GenericArray::default();
+            arr.copy_from_slice(raw);
+
+            Ok(arr)
+        }
+        None => Err(Error::MissingChecksum),
+    }
+}
+
+/// Reads the display title of the directory entry at the start of `entry`, if it is in
+/// namespace `ns` and has MIME type number `mime`.
+fn read_article_title(entry: &[u8], ns: u8, mime: u16) -> Option<String> {
+    let mime_id = u16::from_le_bytes([*entry.first()?, *entry.get(1)?]);
+    if mime_id != mime || *entry.get(3)? != ns {
+        return None;
+    }
+
+    // skip the MIME type, parameter length, namespace, revision, cluster and blob number
+    let rest = entry.get(16..)?;
+    let url_end = rest.iter().position(|b| *b == 0)?;
+    let (url, rest) = (&rest[..url_end], &rest[url_end + 1..]);
+    let title = &rest[..rest.iter().position(|b| *b == 0)?];
+    let title = if title.is_empty() { url } else { title };
+
+    String::from_utf8(title.to_vec()).ok()
+}
+
+/// Computes the digest of `data`, in chunks so large archives are streamed through the hasher.
+fn digest(data: &[u8], algorithm: ChecksumAlgorithm) -> Checksum {
+    match algorithm {
+        ChecksumAlgorithm::Md5 => {
+            let mut hasher = Md5::new();
+            for chunk in data.chunks(1024 * 1024) {
+                hasher.update(chunk);
+            }
+
+            hasher.finalize()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cluster::Compression;
+    use crate::namespace::Namespace;
+    use crate::test_util;
+
+    use super::*;
+    #[test]
+    #[cfg(feature = "mmap")]
+    fn test_zim_ab_all_2017_03() {
+        let zim =
+            Zim::new("fixtures/wikipedia_ab_all_2017-03.zim").expect("failed to parse fixture");
+
+        assert_eq!(zim.header.version_major, 5);
+        let cl0 = zim.get_cluster(0).unwrap();
+        assert_eq!(&cl0.get_blob(0).unwrap()[..], &[97, 98, 107][..]);
+
+        let cl1 = zim.get_cluster(zim.header.cluster_count - 1).unwrap();
+        let b = cl1.get_blob(0).unwrap();
+        assert_eq!(&b[0..10], &[71, 73, 70, 56, 57, 97, 44, 1, 150, 0]);
+        assert_eq!(
+            &b[b.len() - 10..],
+            &[222, 192, 21, 240, 155, 91, 65, 0, 0, 59]
+        );
+
+        assert_eq!(zim.iterate_by_urls().count(), 3111);
+    }
+
+    #[test]
+    #[cfg(feature = "mmap")]
+    fn test_zim_ab_all_maxi_2022_05() {
+        let zim = Zim::new("fixtures/wikipedia_ab_all_maxi_2022-05.zim")
+            .expect("failed to parse fixture");
+
+        assert_eq!(zim.header.version_major, 5);
+        assert_eq!(zim.header.article_count, 9890);
+
+        let cl0 = zim.get_cluster(0).unwrap();
+        assert_eq!(
+            &cl0.get_blob(0).unwrap()[..],
+            &[50, 48, 50, 50, 45, 48, 53, 45, 49, 52][..]
+        );
+        assert_eq!(cl0.compression(), Compression::Zstd);
+
+        let cl1 = zim.get_cluster(zim.header.cluster_count - 1).unwrap();
+        let b = cl1.get_blob(0).unwrap();
+        assert_eq!(&b[0..10], &[15, 13, 88, 97, 112, 105, 97, 110, 32, 71]);
+        assert_eq!(
+            &b[b.len() - 10..],
+            &[148, 79, 82, 254, 154, 242, 15, 122, 255, 0],
+        );
+
+        assert_eq!(zim.iterate_by_urls().count(), 9890);
+    }
+
+    fn assert_send_sync<T: Send + Sync>() {}
+
+    #[test]
+    fn test_send_sync() {
+        assert_send_sync::<Zim>();
+        assert_send_sync::<Cluster<'static>>();
+    }
+
+    #[test]
+    fn test_header_bytes() {
+        for version in [5, 6] {
+            let zim = test_util::sample(version).open("header_bytes");
+
+            let raw = zim.header_bytes();
+            assert_eq!(raw.len(), ZIM_HEADER_SIZE);
+            assert_eq!(&raw[0..4], &ZIM_MAGIC_NUMBER.to_le_bytes());
+            assert_eq!(&raw[4..6], &version.to_le_bytes());
+        }
+    }
+
+    #[test]
+    fn test_from_bytes() {
+        let zim = Zim::from_bytes(test_util::sample(6).build()).unwrap();
+        assert!(zim.file_path.as_os_str().is_empty());
+        zim.verify_checksum().unwrap();
+
+        let entry = zim.get_by_url('A', "Berlin").unwrap().unwrap();
+        assert_eq!(
+            zim.read_content(&entry).unwrap().unwrap(),
+            b"<html>Berlin</html>"
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "mmap")]
+    fn test_sizes() {
+        let path = test_util::sample(5).write("sizes");
+        let zim = Zim::new(&path).unwrap();
+
+        let len = std::fs::metadata(&path).unwrap().len();
+        assert_eq!(zim.file_size(), len);
+        assert_eq!(zim.checksum_pos(), len - 16);
+        assert!(zim.content_size() > 0 && zim.content_size() < len);
+
+        let blobs: u64 = zim
+            .iterate_by_urls()
+            .filter(|e| matches!(e.target, Some(Target::Cluster(_, _))))
+            .map(|e| zim.read_content(&e).unwrap().unwrap().len() as u64)
+            .sum();
+        // two clusters with blob offset tables of 6 and 2 entries
+        assert_eq!(
+            zim.decompressed_size_estimate().unwrap(),
+            blobs + 6 * 4 + 2 * 4
+        );
+    }
+
+    #[test]
+    fn test_decompress_all() {
+        let zim = test_util::sample(5).open("decompress_all");
+
+        let mut calls = Vec::new();
+        zim.decompress_all(|done, total| calls.push((done, total)))
+            .unwrap();
+
+        let total = zim.header.cluster_count;
+        assert_eq!(calls.len(), total as usize);
+        assert_eq!(calls.last(), Some(&(total, total)));
+        // only the compressed cluster ends up in the cache
+        assert_eq!(zim.cache.len(), 1);
+    }
+
+    #[test]
+    fn test_is_supported() {
+        let zim = test_util::sample(5).open("is_supported");
+        assert!(zim.is_supported());
+        assert_eq!(zim.cluster_compression(0).unwrap(), Compression::Lzma2);
+        assert_eq!(zim.cluster_compression(1).unwrap(), Compression::None);
+        assert!(matches!(
+            zim.cluster_compression(2),
+            Err(Error::OutOfBounds)
+        ));
+
+        let mut b = test_util::ArchiveBuilder::new(5);
+        b.cluster(Compression::Zstd, vec![b"a".to_vec()]);
+        b.cluster(Compression::Bzip2, vec![b"b".to_vec()]);
+        let zim = b.open("is_supported_bzip2");
+        assert!(!zim.is_supported());
+
+        let mut zim = test_util::sample(6).open("is_supported_version");
+        zim.header.version_major = 7;
+        assert!(!zim.is_supported());
+    }
+
+    #[test]
+    fn test_clusters() {
+        let mut b = test_util::ArchiveBuilder::new(5);
+        for compression in [Compression::Zstd, Compression::None, Compression::Lzma2] {
+            b.cluster(compression, vec![b"a".to_vec()]);
+        }
+        let zim = b.open("clusters");
+
+        let clusters: Vec<_> = zim.clusters().collect::<Result<_>>().unwrap();
+        assert_eq!(clusters.len(), zim.header.cluster_count as usize);
+        for (idx, cluster) in clusters.iter().enumerate() {
+            assert_eq!(
+                cluster.compression(),
+                zim.get_cluster(idx as u32).unwrap().compression()
+            );
+        }
+    }
+
+    #[test]
+    fn test_clone_cluster_uncompressed() {
+        let mut b = test_util::ArchiveBuilder::new(6);
+        let blobs = vec![b"one".to_vec(), b"two".to_vec(), vec![]];
+        b.cluster(Compression::Zstd, blobs.clone());
+        b.extended_cluster(Compression::Lzma2, blobs.clone());
+        let zim = b.open("clone_cluster_uncompressed");
+
+        for idx in 0..zim.header.cluster_count {
+            let data = zim.clone_cluster_uncompressed(idx).unwrap();
+            let cluster = Cluster::new(&data, &[0], 0, data.len() as u64, 6).unwrap();
+            assert_eq!(cluster.compression(), Compression::None);
+            for (blob_idx, blob) in blobs.iter().enumerate() {
+                assert_eq!(cluster.get_blob(blob_idx as u32).unwrap().as_ref(), blob);
+            }
+        }
+        assert!(matches!(
+            zim.clone_cluster_uncompressed(2),
+            Err(Error::OutOfBounds)
+        ));
+    }
+
+    #[test]
+    fn test_mixed_compression() {
+        let mut b = test_util::ArchiveBuilder::new(6);
+        let codecs = [Compression::Lzma2, Compression::None, Compression::Zstd];
+        for (idx, compression) in codecs.iter().enumerate() {
+            let blobs = vec![
+                format!("{}-0", idx).into_bytes(),
+                format!("{}-1", idx).into_bytes(),
+            ];
+            let c = b.cluster(*compression, blobs);
+            b.article('A', &format!("{}", idx), "", "text/html", c, 1);
+        }
+        let zim = b.open("mixed_compression");
+
+        // read alternately from every cluster, twice, so cached data is reused as well
+        for _ in 0..2 {
+            for (idx, compression) in codecs.iter().enumerate() {
+                let cluster = zim.get_cluster(idx as u32).unwrap();
+                assert_eq!(cluster.compression(), *compression);
+                for blob in 0..2 {
+                    assert_eq!(
+                        cluster.get_blob(blob).unwrap().as_ref(),
+                        format!("{}-{}", idx, blob).as_bytes()
+                    );
+                }
+                let url = format!("{}", idx);
+                assert_eq!(
+                    zim.content_by_url('A', &url).unwrap().unwrap(),
+                    format!("{}-1", idx).as_bytes()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_url_pointers() {
+        let zim = test_util::sample(6).open("url_pointers");
+
+        let pointers = zim.url_pointers().unwrap();
+        assert_eq!(pointers.len(), zim.article_count());
+        assert_eq!(
+            zim.entry_at_offset(pointers[0]).unwrap().url,
+            zim.get_by_url_index(0).unwrap().url
+        );
+    }
+
+    #[test]
+    fn test_try_get_cluster() {
+        let zim = test_util::sample(5).open("try_get_cluster");
+        assert_eq!(zim.cluster_count(), 2);
+
+        let cluster = zim.try_get_cluster(1).unwrap().unwrap();
+        assert_eq!(cluster.compression(), Compression::None);
+        assert!(zim.try_get_cluster(2).unwrap().is_none());
+        assert!(zim.try_get_cluster(u32::MAX).unwrap().is_none());
+        assert!(matches!(zim.get_cluster(2), Err(Error::OutOfBounds)));
+    }
+
+    #[test]
+    fn test_redirect_target() {
+        let zim = test_util::sample(5).open("redirect_target");
+
+        let redirect = zim.get_by_url('A', "NYC").unwrap().unwrap();
+        let target = zim.redirect_target(&redirect).unwrap().unwrap();
+        assert_eq!(target.url, "New York");
+
+        assert!(zim.redirect_target(&target).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_entry_for_blob() {
+        let zim = test_util::sample(6).open("entry_for_blob");
+
+        for entry in zim.iterate_by_urls() {
+            if let Some(Target::Cluster(cluster, blob)) = entry.target {
+                let found = zim.entry_for_blob(cluster, blob).unwrap().unwrap();
+                assert_eq!((found.namespace, found.url), (entry.namespace, entry.url));
+            }
+        }
+        assert!(zim.entry_for_blob(0, 99).unwrap().is_none());
+        assert!(zim.try_clone().blob_index.get().is_some());
+    }
+
+    #[test]
+    fn test_total_content_bytes() {
+        let zim = test_util::sample(5).open("total_content_bytes");
+
+        // the three articles, without the redirect, the image and the metadata
+        let expected = [
+            "<html>Berlin</html>",
+            "<html>Main</html>",
+            "<html>New York</html>",
+        ]
+        .iter()
+        .map(|html| html.len() as u64)
+        .sum::<u64>();
+        let total = zim.total_content_bytes().unwrap();
+        assert_eq!(total, expected);
+        assert!(total < zim.decompressed_size_estimate().unwrap());
+    }
+
+    #[test]
+    fn test_entries_range() {
+        let zim = test_util::sample(6).open("entries_range");
+
+        let page = zim.entries_range(2, 3).unwrap();
+        let urls: Vec<_> = page.into_iter().map(|e| e.url).collect();
+        let expected: Vec<_> = (2..5)
+            .map(|idx| zim.get_by_url_index(idx).unwrap().url)
+            .collect();
+        assert_eq!(urls, expected);
+
+        assert_eq!(zim.entries_range(5, 10).unwrap().len(), 2);
+        assert!(zim.entries_range(7, 1).unwrap().is_empty());
+        assert!(matches!(zim.entries_range(8, 1), Err(Error::OutOfBounds)));
+    }
+
+    #[test]
+    fn test_checksum_algorithm() {
+        for version in [5, 6] {
+            let zim = test_util::sample(version).open("checksum_algorithm");
+            assert_eq!(zim.checksum_algorithm(), ChecksumAlgorithm::Md5);
+            zim.verify_checksum().unwrap();
+        }
+    }
+
+    #[test]
+    fn test_titles() {
+        for version in [5, 6] {
+            let zim = test_util::sample(version).open("titles");
+
+            let titles: Vec<_> = zim.titles().collect();
+            assert_eq!(titles, vec!["Berlin", "Main Page", "New York"]);
+            assert!(titles.windows(2).all(|pair| pair[0] <= pair[1]));
+            assert_eq!(titles.len(), zim.articles().unwrap().count());
+        }
+    }
+
+    #[test]
+    fn test_parse_mode() {
+        let mut data = test_util::sample(5).build();
+        let zim = Zim::from_bytes(data.clone()).unwrap();
+        // give A/NYC an unknown MIME type
+        let offset = zim.url_list[2] as usize;
+        data[offset..offset + 2].copy_from_slice(&[0x34, 0x12]);
+        drop(zim);
+
+        let mut zim = Zim::from_bytes(data).unwrap();
+        assert_eq!(zim.iterate_by_urls().count(), 2);
+        assert!(matches!(
+            zim.entries_by_cluster(),
+            Err(Error::UnknownMimeType)
+        ));
+
+        zim.set_parse_mode(ParseMode::Lenient);
+        let urls: Vec<_> = zim.iterate_by_urls().map(|e| e.url).collect();
+        assert_eq!(urls.len(), 6);
+        assert!(!urls.contains(&"NYC".to_string()));
+        assert_eq!(zim.entries_by_cluster().unwrap().count(), 6);
+        assert_eq!(zim.stats().entries_skipped, 2);
+    }
+
+    #[test]
+    fn test_get_by_url_index_out_of_bounds() {
+        let mut zim = test_util::sample(5).open("get_by_url_index_out_of_bounds");
+        let count = zim.article_count() as u32;
+        assert!(zim.get_by_url_index(count - 1).is_ok());
+        assert!(matches!(
+            zim.get_by_url_index(count),
+            Err(Error::OutOfBounds)
+        ));
+
+        zim.url_list[0] = u64::MAX;
+        assert!(matches!(zim.get_by_url_index(0), Err(Error::OutOfBounds)));
+    }
+
+    #[test]
+    fn test_redirect_and_content_count() {
+        let mut b = test_util::ArchiveBuilder::new(5);
+        let c = b.cluster(Compression::None, vec![b"a".to_vec(), b"b".to_vec()]);
+        b.article('A', "a", "", "text/html", c, 0)
+            .article('A', "b", "", "text/html", c, 1)
+            .redirect('A', "c", "", ('A', "a"));
+        let zim = b.open("redirect_and_content_count");
+
+        assert_eq!(zim.redirect_count().unwrap(), 1);
+        assert_eq!(zim.content_count().unwrap(), 2);
+        assert_eq!(
+            zim.redirect_count().unwrap() + zim.content_count().unwrap(),
+            zim.article_count() as u32
+        );
+        assert_eq!(zim.try_clone().target_counts.get(), Some(&(1, 2)));
+    }
+
+    #[test]
+    fn test_verify_checksum_in_background() {
+        let data = test_util::sample(6).build();
+        let mut corrupted = data.clone();
+        let last = corrupted.len() - 17;
+        corrupted[last] ^= 0xff;
+
+        for (data, valid) in [(data, true), (corrupted, false)] {
+            let zim = Zim::from_bytes(data).unwrap();
+            let (tx, rx) = std::sync::mpsc::channel();
+            zim.verify_checksum_in_background(move |result| tx.send(result).unwrap());
+            // the archive stays usable meanwhile
+            assert!(zim.get_by_url('A', "Berlin").unwrap().is_some());
+
+            let result = rx.recv().unwrap();
+            assert_eq!(result.is_ok(), valid);
+            if !valid {
+                assert!(matches!(result, Err(Error::InvalidChecksum)));
+            }
+        }
+    }
+
+    #[cfg(feature = "mmap")]
+    #[test]
+    fn test_open_verified_lazy() {
+        let path = test_util::sample(5).write("open_verified_lazy");
+        let (tx, rx) = std::sync::mpsc::channel();
+        let zim = Zim::open_verified_lazy(&path, move |result| tx.send(result).unwrap()).unwrap();
+        assert_eq!(zim.article_count(), 7);
+        assert!(rx.recv().unwrap().is_ok());
+    }
+
+    #[test]
+    fn test_set_access_pattern() {
+        let zim = test_util::sample(5).open("set_access_pattern");
+        let in_memory = Zim::from_bytes(test_util::sample(5).build()).unwrap();
+
+        for pattern in [
+            AccessPattern::Sequential,
+            AccessPattern::Random,
+            AccessPattern::WillNeed,
+            AccessPattern::Normal,
+        ] {
+            zim.set_access_pattern(pattern).unwrap();
+            in_memory.set_access_pattern(pattern).unwrap();
+            assert_eq!(
+                zim.content_by_url('A', "Berlin").unwrap().unwrap(),
+                b"<html>Berlin</html>"
+            );
+        }
+    }
+
+    #[test]
+    fn test_namespaces() {
+        let mut b = test_util::ArchiveBuilder::new(6).minor(1);
+        let c = b.cluster(Compression::None, vec![b"a".to_vec(), b"b".to_vec()]);
+        b.article('C', "One", "", "text/html", c, 0)
+            .article('C', "Two", "", "text/html", c, 1)
+            .article('M', "Title", "", "text/plain", c, 0)
+            .article('X', "fulltext/xapian", "", "application/octet-stream", c, 1);
+        let zim = b.open("namespaces");
+        assert_eq!(zim.namespaces().unwrap(), vec!['C', 'M', 'X']);
+
+        let zim = test_util::sample(5).open("namespaces_sample");
+        assert_eq!(zim.namespaces().unwrap(), vec!['A', 'I', 'M']);
+    }
+
+    #[test]
+    fn test_namespace_entry_count() {
+        let zim = test_util::sample(5).open("namespace_entry_count");
+
+        for ns in ['A', 'I', 'M', 'C'] {
+            let expected = zim
+                .iterate_by_urls()
+                .filter(|e| e.namespace as u8 as char == ns)
+                .count();
+            assert_eq!(zim.namespace_entry_count(ns).unwrap(), expected as u32);
+        }
+        assert_eq!(zim.namespace_entry_count('A').unwrap(), 4);
+    }
+
+    #[test]
+    fn test_build_content_index() {
+        let mut b = test_util::ArchiveBuilder::new(5);
+        let c0 = b.cluster(Compression::Zstd, vec![b"same".to_vec(), b"other".to_vec()]);
+        let c1 = b.cluster(Compression::None, vec![b"same".to_vec()]);
+        b.article('A', "a", "", "text/html", c0, 0)
+            .article('A', "b", "", "text/html", c0, 1)
+            .article('A', "c", "", "text/html", c1, 0)
+            .redirect('A', "d", "", ('A', "a"));
+        let zim = b.open("build_content_index");
+
+        let index = zim.build_content_index().unwrap();
+        assert_eq!(index.len(), 2);
+        let same: [u8; 32] = Sha256::digest(b"same").into();
+        assert_eq!(index[&same], vec![0, 2]);
+        let other: [u8; 32] = Sha256::digest(b"other").into();
+        assert_eq!(index[&other], vec![1]);
+    }
+
+    #[test]
+    fn test_all_metadata() {
+        let mut b = test_util::sample(5);
+        let c = b.cluster(Compression::None, vec![vec![0x89, b'P', b'N', b'G', 0xff]]);
+        b.article('M', "Illustration_48x48@1", "", "image/png", c, 0);
+        let zim = b.open("all_metadata");
+
+        let metadata = zim.all_metadata().unwrap();
+        assert_eq!(metadata.len(), 2);
+        assert_eq!(metadata["Title"], "Sample");
+        assert_eq!(metadata["Language"], "en");
+    }
+
+    #[cfg(feature = "mmap")]
+    #[test]
+    fn test_builder_defaults() {
+        let mut data = test_util::sample(5).build();
+        // a broken checksum, which only validation notices
+        let last = data.len() - 17;
+        data[last] ^= 0xff;
+        let path = test_util::write_temp("builder_defaults", &data);
+
+        let plain = Zim::new(&path).unwrap();
+        let built = ZimBuilder::new().open(&path).unwrap();
+        assert_eq!(built.file_path, plain.file_path);
+        assert_eq!(built.header.uuid, plain.header.uuid);
+        assert_eq!(built.cache_size(), plain.cache_size());
+        assert_eq!(built.cache_size(), DEFAULT_CACHE_SIZE);
+        assert_eq!(built.settings.parse_mode, plain.settings.parse_mode);
+        assert_eq!(built.stats(), plain.stats());
+        assert_eq!(
+            built.iterate_by_urls().map(|e| e.url).collect::<Vec<_>>(),
+            plain.iterate_by_urls().map(|e| e.url).collect::<Vec<_>>()
+        );
+
+        let built = ZimBuilder::new()
+            .cache_size(1024)
+            .parse_mode(ParseMode::Lenient)
+            .access_pattern(AccessPattern::Random)
+            .prefetch(true)
+            .open(&path)
+            .unwrap();
+        assert_eq!(built.cache_size(), 1024);
+        assert_eq!(built.settings.parse_mode, ParseMode::Lenient);
+        assert!(matches!(
+            ZimBuilder::new()
+                .validate_on_open(Validations::CHECKSUM)
+                .from_bytes(data),
+            Err(Error::InvalidChecksum)
+        ));
+    }
+
+    #[cfg(feature = "mmap")]
+    #[test]
+    #[allow(deprecated)]
+    fn test_validate_on_open() {
+        let mut data = test_util::sample(5).build();
+        // flip a byte of the uncompressed cluster, which only the checksum notices
+        let last = data.len() - 17;
+        data[last] ^= 0xff;
+        let path = test_util::write_temp("validate_on_open", &data);
+
+        assert!(Zim::open_ro_shared(&path, ZimOptions::default()).is_ok());
+        let options = ZimOptions {
+            validate_on_open: Validations::STRUCTURE | Validations::CHECKSUM,
+            ..ZimOptions::default()
+        };
+        assert!(matches!(
+            Zim::open_ro_shared(&path, options),
+            Err(Error::InvalidChecksum)
+        ));
+
+        let zim = Zim::new(&path).unwrap();
+        zim.validate_with(Validations::STRUCTURE).unwrap();
+        assert!(zim.validate_with(Validations::CHECKSUM).is_err());
+        zim.validate_with(Validations::empty()).unwrap();
+        assert!(Validations::ALL.contains(Validations::STRUCTURE | Validations::CHECKSUM));
+    }
+
+    #[test]
+    fn test_target_at() {
+        for version in [5, 6] {
+            let zim = test_util::sample(version).open("target_at");
+            for idx in 0..zim.article_count() as u32 {
+                assert_eq!(
+                    zim.target_at(idx).unwrap(),
+                    zim.get_by_url_index(idx).unwrap().target
+                );
+            }
+            assert!(matches!(zim.target_at(7), Err(Error::OutOfBounds)));
+        }
+    }
+
+    #[test]
+    fn test_find_duplicate_urls() {
+        let zim = test_util::sample(5).open("find_duplicate_urls_clean");
+        assert!(zim.find_duplicate_urls().unwrap().is_empty());
+
+        let mut b = test_util::ArchiveBuilder::new(5);
+        let c = b.cluster(Compression::None, vec![b"a".to_vec(), b"b".to_vec()]);
+        b.article('A', "Dup", "", "text/html", c, 0)
+            .article('A', "Dup", "", "text/html", c, 1)
+            .article('A', "Dup", "", "text/html", c, 1)
+            .article('A', "Other", "", "text/html", c, 0)
+            .article('M', "Dup", "", "text/plain", c, 0);
+        let zim = b.open("find_duplicate_urls");
+        assert_eq!(
+            zim.find_duplicate_urls().unwrap(),
+            vec![('A', "Dup".to_string())]
+        );
+        assert!(matches!(
+            zim.validate_with(Validations::UNIQUE_URLS),
+            Err(Error::InvalidStructure(_))
+        ));
+    }
 
-/// Read out the the 16 byte long MD5 checksum.
-fn read_checksum(master_view: &Mmap, checksum_pos: u64) -> Result<Checksum> {
-    let checksum_pos = usize::try_from(checksum_pos)?;
-    match master_view.get(checksum_pos..checksum_pos + 16) {
-        Some(raw) => {
-            let mut arr = GenericArray::default();
-            arr.copy_from_slice(raw);
+    #[test]
+    fn test_compute_checksum() {
+        let zim = test_util::sample(6).open("compute_checksum");
+        assert_eq!(&zim.compute_checksum().unwrap(), zim.checksum());
 
-            Ok(arr)
+        let mut data = test_util::sample(6).build();
+        let last = data.len() - 17;
+        data[last] ^= 0xff;
+        let zim = Zim::from_bytes(data).unwrap();
+        assert_ne!(&zim.compute_checksum().unwrap(), zim.checksum());
+        assert!(matches!(zim.verify_checksum(), Err(Error::InvalidChecksum)));
+
+        let mut zim = test_util::sample(6).open("compute_checksum_out_of_bounds");
+        zim.header.checksum_pos = zim.master_view.len() as u64 + 1;
+        assert!(matches!(zim.compute_checksum(), Err(Error::OutOfBounds)));
+        assert!(matches!(zim.verify_checksum(), Err(Error::OutOfBounds)));
+    }
+
+    #[cfg(feature = "time")]
+    #[test]
+    fn test_date() {
+        use time::macros::date;
+
+        assert_eq!(test_util::sample(5).open("date_none").date().unwrap(), None);
+
+        for (value, expected) in [
+            ("2024-02-29", Some(date!(2024 - 02 - 29))),
+            (" 2023-07-01\n", Some(date!(2023 - 07 - 01))),
+            ("2023-02-29", None),
+            ("2023-04-31", None),
+            ("2023-13-01", None),
+            ("2023-7-1", None),
+            ("29/02/2024", None),
+        ] {
+            let mut b = test_util::sample(5);
+            let c = b.cluster(Compression::None, vec![value.as_bytes().to_vec()]);
+            b.article('M', "Date", "", "text/plain", c, 0);
+            let zim = b.open("date");
+            assert_eq!(zim.date().unwrap(), expected, "{:?}", value);
         }
-        None => Err(Error::MissingChecksum),
     }
-}
 
-/// Compute the MD5 checksum of the file.
-fn compute_checksum(path: &Path, checksum_pos: u64) -> Result<Checksum> {
-    let file = File::open(path)?;
-    let mut reader = BufReader::new(file.take(checksum_pos));
-    let mut buffer = vec![0u8; 1024];
-    let mut hasher = Md5::new();
+    #[test]
+    fn test_cluster_ranges() {
+        let zim = test_util::sample(5).open("cluster_ranges");
 
-    loop {
-        let read = reader.read(&mut buffer)?;
-        if read == 0 {
-            break;
+        let ranges = zim.cluster_ranges().unwrap();
+        assert_eq!(ranges.len(), zim.header.cluster_count as usize);
+        assert_eq!(ranges[0].0, zim.cluster_list[0]);
+        for pair in ranges.windows(2) {
+            assert!(pair[0].0 < pair[0].1);
+            assert_eq!(pair[0].1, pair[1].0);
         }
+        assert_eq!(ranges.last().unwrap().1, zim.header.checksum_pos);
+    }
 
-        hasher.update(&buffer[..read]);
+    #[test]
+    fn test_checksum_matches() {
+        let data = test_util::sample(5).build();
+        let stored: [u8; 16] = data[data.len() - 16..].try_into().unwrap();
+        let zim = Zim::from_bytes(data).unwrap();
+
+        assert_eq!(zim.checksum(), &stored);
+        assert!(zim.checksum_matches(&stored));
+        let mut other = stored;
+        other[0] ^= 1;
+        assert!(!zim.checksum_matches(&other));
     }
 
-    Ok(hasher.finalize())
-}
+    #[test]
+    fn test_entries_by_cluster() {
+        let zim = test_util::sample(6).open("entries_by_cluster");
 
-#[cfg(test)]
-mod tests {
-    use crate::cluster::Compression;
+        let targets: Vec<_> = zim
+            .entries_by_cluster()
+            .unwrap()
+            .map(|e| match e.target {
+                Some(Target::Cluster(cluster, blob)) => (cluster, blob),
+                other => panic!("unexpected target {:?}", other),
+            })
+            .collect();
+        assert_eq!(targets.len(), 6);
+        assert!(targets.windows(2).all(|pair| pair[0] <= pair[1]));
+    }
 
-    use super::*;
     #[test]
-    fn test_zim_ab_all_2017_03() {
-        let zim =
-            Zim::new("fixtures/wikipedia_ab_all_2017-03.zim").expect("failed to parse fixture");
+    fn test_blobs_in_namespace() {
+        let mut b = test_util::ArchiveBuilder::new(5);
+        let first = b.cluster(Compression::Lzma2, vec![b"c".to_vec(), b"b".to_vec()]);
+        let second = b.cluster(Compression::None, vec![b"a".to_vec()]);
+        b.article('A', "page", "", "text/html", first, 0)
+            .article('I', "a.png", "", "image/png", second, 0)
+            .article('I', "b.png", "", "image/png", first, 1)
+            .article('I', "c.png", "", "image/png", first, 0)
+            .redirect('I', "alias.png", "", ('I', "a.png"));
+        let zim = b.open("blobs_in_namespace");
 
-        assert_eq!(zim.header.version_major, 5);
-        let cl0 = zim.get_cluster(0).unwrap();
-        assert_eq!(&cl0.get_blob(0).unwrap()[..], &[97, 98, 107][..]);
+        let blobs: Vec<_> = zim
+            .blobs_in_namespace('I')
+            .map(|item| {
+                let (entry, data) = item.unwrap();
+                (entry.url, data)
+            })
+            .collect();
+        assert_eq!(
+            blobs,
+            vec![
+                ("c.png".to_string(), b"c".to_vec()),
+                ("b.png".to_string(), b"b".to_vec()),
+                ("a.png".to_string(), b"a".to_vec()),
+                ("alias.png".to_string(), vec![]),
+            ]
+        );
+        assert_eq!(zim.stats().clusters_decompressed, 1);
+        assert_eq!(zim.blobs_in_namespace('X').count(), 0);
+    }
 
-        let cl1 = zim.get_cluster(zim.header.cluster_count - 1).unwrap();
-        let b = cl1.get_blob(0).unwrap();
-        assert_eq!(&b[0..10], &[71, 73, 70, 56, 57, 97, 44, 1, 150, 0]);
+    #[cfg(feature = "tar")]
+    fn read_tar(data: &[u8]) -> Vec<(String, Vec<u8>)> {
+        use std::io::Read;
+
+        let mut archive = tar::Archive::new(data);
+        archive
+            .entries()
+            .unwrap()
+            .map(|entry| {
+                let mut entry = entry.unwrap();
+                let path = entry.path().unwrap().to_str().unwrap().to_string();
+                let mut data = Vec::new();
+                entry.read_to_end(&mut data).unwrap();
+                (path, data)
+            })
+            .collect()
+    }
+
+    #[cfg(feature = "tar")]
+    #[test]
+    fn test_extract_to_tar() {
+        let zim = test_util::sample(5).open("extract_to_tar");
+
+        let mut out = Vec::new();
+        zim.extract_to_tar(&mut out).unwrap();
+        let files = read_tar(&out);
+
+        let content = zim
+            .iterate_by_urls()
+            .filter(|e| matches!(e.target, Some(Target::Cluster(_, _))))
+            .count();
+        assert_eq!(files.len(), content);
+        let (_, data) = files.iter().find(|(path, _)| path == "A/New York").unwrap();
+        assert_eq!(data, b"<html>New York</html>");
+        let (_, data) = files.iter().find(|(path, _)| path == "I/logo.png").unwrap();
+        assert_eq!(data, &[0x89, b'P', b'N', b'G', 0, 1, 2]);
+
+        // hostile urls stay within the destination
+        let mut b = test_util::ArchiveBuilder::new(5);
+        let c = b.cluster(Compression::None, vec![b"x".to_vec(), b"y".to_vec()]);
+        b.article('A', "../../etc/passwd", "", "text/plain", c, 0)
+            .article('A', "/a/./b\\..", "", "text/plain", c, 1);
+        let zim = b.open("extract_to_tar_escape");
+        let mut out = Vec::new();
+        zim.extract_to_tar(&mut out).unwrap();
+        let paths: Vec<_> = read_tar(&out).into_iter().map(|(path, _)| path).collect();
+        assert_eq!(paths, vec!["A/%2E%2E/%2E%2E/etc/passwd", "A/a/%2E/b%5C.."]);
+    }
+
+    #[test]
+    #[cfg(feature = "mmap")]
+    fn test_from_mmap() {
+        let archive = test_util::sample(5).build();
+        // place the archive in the middle of a larger file and map only its region
+        let mut data = vec![0xff; 4096];
+        data.extend_from_slice(&archive);
+        data.extend_from_slice(&[0xff; 100]);
+        let path = test_util::write_temp("from_mmap", &data);
+
+        let f = File::open(&path).unwrap();
+        let map = unsafe {
+            memmap::MmapOptions::new()
+                .offset(4096)
+                .len(archive.len())
+                .map(&f)
+                .unwrap()
+        };
+        let zim = Zim::from_mmap(map).unwrap();
+        assert!(zim.file_path.as_os_str().is_empty());
+        assert_eq!(zim.file_size(), archive.len() as u64);
+        zim.verify_checksum().unwrap();
+
+        let entry = zim.get_by_url('A', "Berlin").unwrap().unwrap();
         assert_eq!(
-            &b[b.len() - 10..],
-            &[222, 192, 21, 240, 155, 91, 65, 0, 0, 59]
+            zim.read_content(&entry).unwrap().unwrap(),
+            b"<html>Berlin</html>"
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "mmap")]
+    fn test_reopen() {
+        let path = test_util::sample(5).write("reopen");
+        let mut zim = ZimBuilder::new()
+            .cache_size(1024 * 1024)
+            .access_pattern(AccessPattern::Random)
+            .open(&path)
+            .unwrap();
+        zim.set_parse_mode(ParseMode::Lenient);
+        assert_eq!(
+            zim.content_by_url('A', "Berlin").unwrap().unwrap(),
+            b"<html>Berlin</html>"
         );
 
-        assert_eq!(zim.iterate_by_urls().count(), 3111);
+        // replace the file the way mirror tools do, with an atomic rename
+        let mut b = test_util::ArchiveBuilder::new(6);
+        let c = b.cluster(Compression::Zstd, vec![b"<html>Berlin 2</html>".to_vec()]);
+        b.article('A', "Berlin", "Berlin", "text/html", c, 0);
+        let replacement = b.write("reopen_replacement");
+        std::fs::rename(&replacement, &path).unwrap();
+
+        zim.reopen().unwrap();
+        assert_eq!(zim.file_path, path);
+        assert_eq!(zim.cache_size(), 1024 * 1024);
+        assert_eq!(zim.settings.parse_mode, ParseMode::Lenient);
+        assert_eq!(zim.header.version_major, 6);
+        assert_eq!(zim.article_count(), 1);
+        assert_eq!(
+            zim.content_by_url('A', "Berlin").unwrap().unwrap(),
+            b"<html>Berlin 2</html>"
+        );
+
+        let mut in_memory = Zim::from_bytes(test_util::sample(5).build()).unwrap();
+        assert!(in_memory.reopen().is_err());
     }
 
     #[test]
-    fn test_zim_ab_all_maxi_2022_05() {
-        let zim = Zim::new("fixtures/wikipedia_ab_all_maxi_2022-05.zim")
-            .expect("failed to parse fixture");
+    #[cfg(feature = "mmap")]
+    fn test_open_at() {
+        let archive = test_util::sample(6).build();
+        let mut data = b"#!/bin/sh\nexit 0\n".to_vec();
+        data.extend_from_slice(&archive);
+        let offset = data.len() as u64 - archive.len() as u64;
+        let path = test_util::write_temp("open_at", &data);
 
-        assert_eq!(zim.header.version_major, 5);
-        assert_eq!(zim.header.article_count, 9890);
+        let zim = Zim::open_at(&path, offset, None).unwrap();
+        assert_eq!(zim.raw(), &archive[..]);
+        zim.validate().unwrap();
+        assert_eq!(
+            zim.content_by_url('A', "NYC").unwrap().unwrap(),
+            b"<html>New York</html>"
+        );
 
-        let cl0 = zim.get_cluster(0).unwrap();
+        // followed by more data
+        data.extend_from_slice(&[0xff; 64]);
+        let path = test_util::write_temp("open_at_len", &data);
+        let zim = Zim::open_at(&path, offset, Some(archive.len() as u64)).unwrap();
+        zim.validate().unwrap();
+
+        assert!(Zim::open_at(&path, offset, None)
+            .unwrap()
+            .validate()
+            .is_err());
+        assert!(matches!(
+            Zim::open_at(&path, offset, Some(data.len() as u64)),
+            Err(Error::OutOfBounds)
+        ));
+        assert!(matches!(
+            Zim::open_at(&path, data.len() as u64 + 1, None),
+            Err(Error::OutOfBounds)
+        ));
+    }
+
+    #[test]
+    #[cfg(feature = "mmap")]
+    #[allow(deprecated)]
+    fn test_open_ro_shared() {
+        let path = test_util::sample(6).write("open_ro_shared");
+        let plain = Zim::new(&path).unwrap();
+        let options = ZimOptions {
+            prefetch: true,
+            ..ZimOptions::default()
+        };
+        let prefetched = Zim::open_ro_shared(&path, options).unwrap();
+
+        assert_eq!(plain.header_bytes(), prefetched.header_bytes());
+        assert_eq!(plain.url_list, prefetched.url_list);
+        assert_eq!(plain.cluster_list, prefetched.cluster_list);
+        for (a, b) in plain.iterate_by_urls().zip(prefetched.iterate_by_urls()) {
+            assert_eq!(a.url, b.url);
+            assert_eq!(
+                plain.read_content(&a).unwrap(),
+                prefetched.read_content(&b).unwrap()
+            );
+        }
+        prefetched.verify_checksum().unwrap();
+    }
+
+    #[test]
+    fn test_stats() {
+        let zim = test_util::sample(6).open("stats");
+        assert_eq!(zim.stats(), ZimStats::default());
+
+        for _ in 0..2 {
+            let cluster = zim.get_cluster(0).unwrap();
+            assert_eq!(
+                cluster.get_blob(0).unwrap().as_ref(),
+                b"<html>Berlin</html>"
+            );
+        }
+        let stats = zim.stats();
+        assert_eq!(stats.cache_misses, 1);
+        assert_eq!(stats.cache_hits, 1);
+        assert_eq!(stats.clusters_decompressed, 1);
         assert_eq!(
-            &cl0.get_blob(0).unwrap()[..],
-            &[50, 48, 50, 50, 45, 48, 53, 45, 49, 52][..]
+            stats.bytes_decompressed,
+            zim.get_cluster(0).unwrap().decompressed_size().unwrap()
         );
-        assert_eq!(cl0.compression(), Compression::Zstd);
 
-        let cl1 = zim.get_cluster(zim.header.cluster_count - 1).unwrap();
-        let b = cl1.get_blob(0).unwrap();
-        assert_eq!(&b[0..10], &[15, 13, 88, 97, 112, 105, 97, 110, 32, 71]);
+        // the uncompressed cluster bypasses the cache
+        zim.get_cluster(1).unwrap().get_blob(0).unwrap();
+        assert_eq!(zim.stats().cache_misses, 1);
+    }
+
+    #[test]
+    fn test_layout_resources() {
+        let mut b = test_util::ArchiveBuilder::new(5);
+        let c = b.cluster(
+            Compression::None,
+            vec![b"body {}".to_vec(), b"<html></html>".to_vec()],
+        );
+        b.article('A', "Page", "Page", "text/html", c, 1).article(
+            '-',
+            "style.css",
+            "",
+            "text/css",
+            c,
+            0,
+        );
+        let zim = b.open("layout_resources");
+
+        let entry = zim.get_by_url('-', "style.css").unwrap().unwrap();
+        assert_eq!(entry.namespace, Namespace::Layout);
         assert_eq!(
-            &b[b.len() - 10..],
-            &[148, 79, 82, 254, 154, 242, 15, 122, 255, 0],
+            zim.content_by_url('-', "style.css").unwrap().unwrap(),
+            b"body {}"
         );
 
-        assert_eq!(zim.iterate_by_urls().count(), 9890);
+        let urls: Vec<_> = zim.layout_resources().unwrap().map(|e| e.url).collect();
+        assert_eq!(urls, vec!["style.css"]);
+        assert_eq!(
+            test_util::sample(5)
+                .open("layout_resources_none")
+                .layout_resources()
+                .unwrap()
+                .count(),
+            0
+        );
+
+        // a broken entry isn't silently dropped: strict mode stops, lenient mode counts it
+        let mut b = test_util::ArchiveBuilder::new(5);
+        let c = b.cluster(Compression::None, vec![b"x".to_vec()]);
+        for url in ["a.css", "b.css", "c.css", "d.js", "e.js"] {
+            b.article('-', url, "", "text/css", c, 0);
+        }
+        b.article('A', "Page", "", "text/html", c, 0);
+        let mut zim = b.open("layout_resources_broken");
+        // the binary searches for the namespace bounds don't read this entry
+        zim.url_list[2] = u64::MAX;
+        let urls =
+            |zim: &Zim| -> Vec<String> { zim.layout_resources().unwrap().map(|e| e.url).collect() };
+        assert_eq!(urls(&zim), vec!["a.css", "b.css"]);
+        zim.set_parse_mode(ParseMode::Lenient);
+        assert_eq!(urls(&zim), vec!["a.css", "b.css", "d.js", "e.js"]);
+        assert_eq!(zim.stats().entries_skipped, 1);
+    }
+
+    #[test]
+    fn test_clear_cache() {
+        let zim = test_util::sample(6).open("clear_cache");
+        let expected = b"<html>Berlin</html>";
+        assert_eq!(&*zim.get_cluster(0).unwrap().get_blob(0).unwrap(), expected);
+        assert_eq!(zim.cache.len(), 1);
+
+        zim.clear_cache();
+        assert_eq!(zim.cache.len(), 0);
+
+        // the cluster is decompressed again
+        assert_eq!(&*zim.get_cluster(0).unwrap().get_blob(0).unwrap(), expected);
+        assert_eq!(zim.cache.len(), 1);
+    }
+
+    #[test]
+    fn test_try_clone() {
+        let zim = test_util::sample(6).open("try_clone");
+        let other = zim.try_clone();
+        assert!(Arc::ptr_eq(&zim.master_view, &other.master_view));
+        assert!(Arc::ptr_eq(&zim.cache, &other.cache));
+
+        let a = zim.get_by_url('A', "Berlin").unwrap().unwrap();
+        let b = other.get_by_url('A', "Berlin").unwrap().unwrap();
+        assert_eq!(a.target, b.target);
+        assert_eq!(
+            zim.read_content(&a).unwrap(),
+            other.read_content(&b).unwrap()
+        );
+        // the blob was decompressed through the first handle, the second reuses it
+        assert_eq!(zim.cache.len(), 1);
+        assert_eq!(other.cache.len(), 1);
+    }
+
+    #[test]
+    fn test_iteration_order() {
+        let mut b = test_util::ArchiveBuilder::new(5);
+        let c = b.cluster(Compression::None, vec![b"x".to_vec()]);
+        for (ns, url) in [
+            ('M', "Title"),
+            ('A', "b"),
+            ('-', "favicon"),
+            ('A', "B"),
+            ('I', "a.png"),
+            ('A', "ab"),
+            ('A', "a"),
+            ('A', "\u{e9}t\u{e9}"),
+        ] {
+            b.article(ns, url, "", "text/html", c, 0);
+        }
+        let built = b.open("iteration_order");
+        let sample = test_util::sample(6).open("iteration_order_sample");
+
+        for zim in [built, sample] {
+            let keys: Vec<(u8, Vec<u8>)> = zim
+                .iterate_by_urls()
+                .map(|e| (e.namespace as u8, e.url_bytes().to_vec()))
+                .collect();
+            assert_eq!(keys.len(), zim.article_count());
+            assert!(keys.windows(2).all(|w| w[0] < w[1]), "{:?}", keys);
+        }
+    }
+
+    #[test]
+    fn test_entry_at_offset() {
+        let zim = test_util::sample(5).open("entry_at_offset");
+        let entry = zim.entry_at_offset(zim.url_list[0]).unwrap();
+        let expected = zim.get_by_url_index(0).unwrap();
+        assert_eq!(entry.url, expected.url);
+        assert_eq!(entry.title, expected.title);
+        assert_eq!(entry.target, expected.target);
+
+        assert!(matches!(
+            zim.entry_at_offset(zim.file_size()),
+            Err(Error::OutOfBounds)
+        ));
+        assert!(zim.entry_at_offset(u64::MAX).is_err());
+    }
+
+    #[test]
+    fn test_raw() {
+        let data = test_util::sample(5).build();
+        let zim = Zim::from_bytes(data.clone()).unwrap();
+        assert_eq!(zim.raw().len() as u64, zim.file_size());
+        assert_eq!(zim.raw()[0..4], ZIM_MAGIC_NUMBER.to_le_bytes());
+        assert_eq!(zim.raw(), &data[..]);
+    }
+
+    #[test]
+    fn test_pointer_counts() {
+        let mut zim = test_util::sample(6).open("pointer_counts");
+        assert_eq!(zim.url_pointer_count() as usize, zim.article_count());
+        assert_eq!(zim.title_pointer_count() as usize, zim.article_count());
+        assert_eq!(zim.url_pointer_count(), zim.header.article_count);
+
+        zim.article_list.pop();
+        assert_eq!(zim.title_pointer_count(), zim.header.article_count - 1);
+        assert!(matches!(zim.validate(), Err(Error::InvalidStructure(_))));
+    }
+
+    #[test]
+    fn test_validate_against_len() {
+        let data = test_util::sample(5).build();
+        let len = data.len() as u64;
+        let header = Zim::from_bytes(data.clone()).unwrap().header;
+        header.validate_against_len(len).unwrap();
+
+        let invalid = vec![
+            ZimHeader {
+                checksum_pos: len - 15,
+                ..header.clone()
+            },
+            ZimHeader {
+                checksum_pos: u64::MAX - 8,
+                ..header.clone()
+            },
+            ZimHeader {
+                url_ptr_pos: len,
+                ..header.clone()
+            },
+            ZimHeader {
+                title_ptr_pos: header.checksum_pos - 4,
+                ..header.clone()
+            },
+            ZimHeader {
+                cluster_ptr_pos: u64::MAX,
+                ..header.clone()
+            },
+            ZimHeader {
+                mime_list_pos: header.checksum_pos,
+                ..header.clone()
+            },
+            ZimHeader {
+                article_count: u32::MAX,
+                ..header.clone()
+            },
+        ];
+        for header in invalid {
+            assert!(matches!(
+                header.validate_against_len(len),
+                Err(Error::InvalidHeader)
+            ));
+        }
+
+        // checked when opening, before any pointer list is read
+        let mut broken = data.clone();
+        broken[32..40].copy_from_slice(&len.to_le_bytes());
+        assert!(matches!(Zim::from_bytes(broken), Err(Error::InvalidHeader)));
+        let mut truncated = data;
+        truncated.truncate(truncated.len() - 8);
+        assert!(matches!(
+            Zim::from_bytes(truncated),
+            Err(Error::InvalidHeader)
+        ));
+    }
+
+    #[test]
+    fn test_validate() {
+        for version in [5, 6] {
+            test_util::sample(version)
+                .open("validate")
+                .validate()
+                .unwrap();
+        }
+
+        let good = test_util::sample(5).build();
+        let header = Zim::from_bytes(good.clone()).unwrap().header;
+        let corrupt = |f: &dyn Fn(&mut Vec<u8>)| {
+            let mut data = good.clone();
+            f(&mut data);
+            Zim::from_bytes(data).unwrap().validate().unwrap_err()
+        };
+        let structure = |err: Error, expected: &str| match err {
+            Error::InvalidStructure(msg) => assert!(msg.contains(expected), "{}", msg),
+            err => panic!("unexpected error {:?}", err),
+        };
+
+        // swap the two cluster offsets
+        let pos = header.cluster_ptr_pos as usize;
+        structure(
+            corrupt(&|d| {
+                let (first, second) = d[pos..pos + 16].split_at_mut(8);
+                first.swap_with_slice(second);
+            }),
+            "cluster 1",
+        );
+        let pos = header.title_ptr_pos as usize;
+        structure(
+            corrupt(&|d| d[pos..pos + 4].copy_from_slice(&99u32.to_le_bytes())),
+            "title pointer 0",
+        );
+        let pos = header.url_ptr_pos as usize + 8;
+        structure(
+            corrupt(&|d| d[pos..pos + 8].copy_from_slice(&u64::MAX.to_le_bytes())),
+            "URL pointer 1",
+        );
+        let pos = header.checksum_pos as usize - 1;
+        assert!(matches!(
+            corrupt(&|d| d[pos] ^= 0xff),
+            Error::InvalidChecksum
+        ));
+        structure(corrupt(&|d| d.push(0)), "checksum");
+
+        // the magic number is checked as well, even if the archive was already opened
+        let mut zim = Zim::from_bytes(good.clone()).unwrap();
+        let mut data = good.clone();
+        data[0] ^= 0xff;
+        zim.master_view = Arc::new(Storage::Bytes(data));
+        assert!(matches!(zim.validate(), Err(Error::InvalidMagicNumber)));
+    }
+
+    #[test]
+    #[cfg(feature = "rayon")]
+    fn test_par_map_entries() {
+        let zim = test_util::sample(6).open("par_map_entries");
+        let mut parallel = zim.par_map_entries(|e| e.url.len()).unwrap();
+        let mut serial: Vec<_> = zim.iterate_by_urls().map(|e| e.url.len()).collect();
+        assert_eq!(parallel.len(), zim.article_count());
+        parallel.sort_unstable();
+        serial.sort_unstable();
+        assert_eq!(parallel, serial);
+
+        // a broken entry is an error, unless lenient parsing skips it
+        let mut zim = test_util::sample(6).open("par_map_entries_broken");
+        zim.url_list[2] = u64::MAX;
+        assert!(matches!(
+            zim.par_map_entries(|e| e.url),
+            Err(Error::OutOfBounds)
+        ));
+        zim.set_parse_mode(ParseMode::Lenient);
+        let urls = zim.par_map_entries(|e| e.url).unwrap();
+        assert_eq!(urls.len(), zim.article_count() - 1);
+        assert!(!urls.contains(&"NYC".to_string()));
+        assert_eq!(zim.stats().entries_skipped, 1);
+    }
+
+    #[test]
+    fn test_get_by_url() {
+        let zim = test_util::sample(5).open("get_by_url");
+
+        let entry = zim.get_by_url('A', "New York").unwrap().unwrap();
+        assert_eq!(entry.title, "New York");
+        assert_eq!(zim.get_by_url('M', "Title").unwrap().unwrap().url, "Title");
+        assert!(zim.get_by_url('A', "Paris").unwrap().is_none());
+        assert!(zim.get_by_url('B', "Berlin").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_mime_index() {
+        let zim = test_util::sample(5).open("mime_index");
+
+        let idx = zim.mime_index("text/html").unwrap();
+        assert_eq!(zim.mime_types()[idx as usize], "text/html");
+        assert_eq!(
+            zim.get_mimetype(idx),
+            Some(MimeType::Type("text/html".into()))
+        );
+        assert_eq!(zim.mime_index("application/pdf"), None);
+    }
+
+    #[test]
+    fn test_url_index_of_title() {
+        let zim = test_util::sample(5).open("url_index_of_title");
+        for entry in zim.iterate_by_urls() {
+            let title = if entry.title.is_empty() {
+                &entry.url
+            } else {
+                &entry.title
+            };
+            let ns = entry.namespace as u8 as char;
+            let idx = zim.url_index_of_title(ns, title).unwrap().unwrap();
+            let found = zim.get_by_url_index(idx).unwrap();
+            assert_eq!((found.url, found.target), (entry.url, entry.target));
+        }
+
+        // the title differs from the URL
+        let idx = zim.url_index_of_title('A', "Main Page").unwrap().unwrap();
+        assert_eq!(zim.get_by_url_index(idx).unwrap().url, "Main_Page");
+        assert_eq!(zim.url_index_of_title('A', "Main_Page").unwrap(), None);
+        assert_eq!(zim.url_index_of_title('M', "Berlin").unwrap(), None);
+    }
+
+    #[test]
+    fn test_content_by_url() {
+        let zim = test_util::sample(5).open("content_by_url");
+        assert_eq!(
+            zim.content_by_url('A', "Berlin").unwrap().unwrap(),
+            b"<html>Berlin</html>"
+        );
+        assert_eq!(
+            zim.content_by_url('A', "NYC").unwrap().unwrap(),
+            b"<html>New York</html>"
+        );
+        assert_eq!(zim.content_by_url('A', "Paris").unwrap(), None);
+    }
+
+    #[test]
+    fn test_stream_by_url() {
+        use std::io::Read;
+
+        let zim = test_util::sample(6).open("stream_by_url");
+        for url in ["Berlin", "NYC", "Main_Page"] {
+            let (mime, mut reader) = zim.stream_by_url('A', url).unwrap().unwrap();
+            assert_eq!(mime, MimeType::Type("text/html".into()));
+            let mut streamed = Vec::new();
+            reader.read_to_end(&mut streamed).unwrap();
+            assert_eq!(streamed, zim.content_by_url('A', url).unwrap().unwrap());
+        }
+        assert!(zim.stream_by_url('A', "Paris").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_read_range() {
+        let zim = test_util::sample(5).open("read_range");
+        let (mime, data) = zim.read_range('A', "NYC", 6..14).unwrap().unwrap();
+        assert_eq!(mime, MimeType::Type("text/html".into()));
+        assert_eq!(data, b"New York");
+
+        let (mime, data) = zim.read_range('I', "logo.png", 1..4).unwrap().unwrap();
+        assert_eq!(mime, MimeType::Type("image/png".into()));
+        assert_eq!(data, b"PNG");
+
+        let len = zim.content_by_url('A', "Berlin").unwrap().unwrap().len();
+        assert!(zim.read_range('A', "Berlin", 0..len).unwrap().is_some());
+        assert!(matches!(
+            zim.read_range('A', "Berlin", 0..len + 1),
+            Err(Error::OutOfBounds)
+        ));
+        assert!(zim.read_range('A', "Paris", 0..1).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_search_urls() {
+        let zim = test_util::sample(5).open("search_urls");
+        let urls = |found: Vec<DirectoryEntry>| -> Vec<String> {
+            found.into_iter().map(|e| e.url).collect()
+        };
+        assert_eq!(urls(zim.search_urls("York", 10)), vec!["New York"]);
+        assert_eq!(
+            urls(zim.search_urls("a", 10)),
+            vec!["Main_Page", "Language"]
+        );
+        assert_eq!(urls(zim.search_urls("e", 2)), vec!["Berlin", "Main_Page"]);
+        assert!(zim.search_urls("Paris", 10).is_empty());
+        assert!(zim.search_urls("e", 0).is_empty());
+    }
+
+    #[test]
+    fn test_namespace_range() {
+        let zim = test_util::sample(5).open("namespace_range");
+        assert_eq!(zim.namespace_range('A').unwrap(), 0..4);
+        assert_eq!(zim.namespace_range('I').unwrap(), 4..5);
+        assert_eq!(zim.namespace_range('M').unwrap(), 5..7);
+        assert_eq!(zim.namespace_range('B').unwrap(), 4..4);
+        assert_eq!(zim.namespace_range('X').unwrap(), 7..7);
+    }
+
+    #[test]
+    fn test_articles() {
+        let zim = test_util::sample(5).open("articles");
+
+        let expected: Vec<_> = zim
+            .iterate_by_urls()
+            .filter(|e| {
+                e.namespace as u8 == b'A' && e.mime_type == MimeType::Type("text/html".into())
+            })
+            .map(|e| e.url)
+            .collect();
+        let articles: Vec<_> = zim.articles().unwrap().map(|e| e.url).collect();
+        assert_eq!(articles, expected);
+        assert_eq!(articles, vec!["Berlin", "Main_Page", "New York"]);
+
+        // a broken entry isn't silently dropped: strict mode stops, lenient mode counts it
+        let mut zim = test_util::sample(5).open("articles_broken");
+        zim.url_list[2] = u64::MAX;
+        let urls: Vec<_> = zim.articles().unwrap().map(|e| e.url).collect();
+        assert_eq!(urls, vec!["Berlin", "Main_Page"]);
+        zim.set_parse_mode(ParseMode::Lenient);
+        let urls: Vec<_> = zim.articles().unwrap().map(|e| e.url).collect();
+        assert_eq!(urls, vec!["Berlin", "Main_Page", "New York"]);
+        assert_eq!(zim.stats().entries_skipped, 1);
+    }
+
+    #[cfg(feature = "rand")]
+    #[test]
+    fn test_random_article() {
+        use rand::rngs::StdRng;
+        use rand::SeedableRng;
+
+        let zim = test_util::sample(5).open("random_article");
+        let articles: Vec<_> = zim.articles().unwrap().map(|e| e.url).collect();
+
+        let pick = |seed| {
+            let mut rng = StdRng::seed_from_u64(seed);
+            (0..10)
+                .map(|_| zim.random_article(&mut rng).unwrap().unwrap().url)
+                .collect::<Vec<_>>()
+        };
+        let first = pick(1);
+        assert_eq!(first, pick(1));
+        assert!(first.iter().all(|url| articles.contains(url)));
+
+        // redirects don't make their target more likely
+        let mut b = test_util::ArchiveBuilder::new(5);
+        let c = b.cluster(Compression::None, vec![b"a".to_vec(), b"b".to_vec()]);
+        b.article('A', "a", "", "text/html", c, 0)
+            .article('A', "b", "", "text/html", c, 1);
+        for i in 0..8 {
+            b.redirect('A', &format!("r{}", i), "", ('A', "a"));
+        }
+        let zim = b.open("random_article_uniform");
+        let mut rng = StdRng::seed_from_u64(2);
+        let picked_a = (0..2000)
+            .filter(|_| zim.random_article(&mut rng).unwrap().unwrap().url == "a")
+            .count();
+        assert!((900..1100).contains(&picked_a), "{}", picked_a);
+
+        let mut b = test_util::ArchiveBuilder::new(5);
+        let c = b.cluster(Compression::None, vec![b"en".to_vec()]);
+        b.article('M', "Language", "", "text/plain", c, 0);
+        let empty = b.open("random_article_empty");
+        let mut rng = StdRng::seed_from_u64(1);
+        assert!(empty.random_article(&mut rng).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_mime_list_raw() {
+        let zim = test_util::sample(5).open("mime_list_raw");
+
+        assert_eq!(zim.mime_list_range().start, zim.header.mime_list_pos);
+        let raw = zim.mime_list_raw();
+        assert!(raw.ends_with(&[0, 0]));
+        let parsed: Vec<_> = raw[..raw.len() - 2]
+            .split(|b| *b == 0)
+            .map(|m| String::from_utf8(m.to_vec()).unwrap())
+            .collect();
+        assert_eq!(parsed, zim.mime_types());
+    }
+
+    #[test]
+    fn test_mime_histogram() {
+        let zim = test_util::sample(5).open("mime_histogram_scan");
+        let (scanned, source) = zim.mime_histogram().unwrap();
+        assert_eq!(source, MimeHistogramSource::Scan);
+        assert_eq!(scanned.len(), 2);
+        assert_eq!(scanned[&MimeType::Type("text/html".into())], 3);
+        assert_eq!(scanned[&MimeType::Type("image/png".into())], 1);
+
+        // a counter as a writer would fill it agrees with the scan
+        let mut b = test_util::sample(5);
+        let c = b.cluster(Compression::None, vec![b"image/png=1;text/html=3".to_vec()]);
+        b.article('M', "Counter", "", "text/plain", c, 0);
+        let zim = b.open("mime_histogram_matching");
+        assert_eq!(
+            zim.mime_histogram().unwrap(),
+            (scanned.clone(), MimeHistogramSource::Counter)
+        );
+
+        // an undecodable counter is ignored
+        let mut b = test_util::sample(5);
+        let c = b.cluster(Compression::None, vec![b"text/html=\xff".to_vec()]);
+        b.article('M', "Counter", "", "text/plain", c, 0);
+        let zim = b.open("mime_histogram_invalid");
+        assert_eq!(
+            zim.mime_histogram().unwrap(),
+            (scanned, MimeHistogramSource::Scan)
+        );
+
+        let mut b = test_util::sample(5);
+        let c = b.cluster(
+            Compression::None,
+            vec![b"text/html=120;image/png=7".to_vec()],
+        );
+        b.article('M', "Counter", "", "text/plain", c, 0);
+        let zim = b.open("mime_histogram_counter");
+        let (histogram, source) = zim.mime_histogram().unwrap();
+        assert_eq!(source, MimeHistogramSource::Counter);
+        assert_eq!(histogram[&MimeType::Type("text/html".into())], 120);
+        assert_eq!(histogram[&MimeType::Type("image/png".into())], 7);
+    }
+
+    #[test]
+    fn test_illustration() {
+        let mut b = test_util::ArchiveBuilder::new(6);
+        let c = b.cluster(
+            Compression::None,
+            vec![b"new icon".to_vec(), b"old icon".to_vec()],
+        );
+        b.article('M', "Illustration_48x48@1", "", "image/png", c, 0)
+            .article('-', "favicon", "", "image/png", c, 1);
+        let zim = b.open("illustration");
+        assert_eq!(zim.illustration(48).unwrap().unwrap(), b"new icon");
+        assert_eq!(zim.illustration(96).unwrap().unwrap(), b"old icon");
+
+        let mut b = test_util::ArchiveBuilder::new(5);
+        let c = b.cluster(Compression::None, vec![b"legacy icon".to_vec()]);
+        b.article('I', "favicon", "", "image/png", c, 0);
+        let zim = b.open("illustration_legacy");
+        assert_eq!(zim.illustration(48).unwrap().unwrap(), b"legacy icon");
+
+        let zim = test_util::sample(5).open("illustration_none");
+        assert!(zim.illustration(48).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_icon() {
+        // builds an archive with the given icon candidates, and a main page linking to `I/icon`
+        let archive = |name: &str, candidates: &[(char, &str)]| {
+            let mut b = test_util::ArchiveBuilder::new(5);
+            let page = br#"<html><head><LINK href="../I/icon" rel="shortcut icon"></head></html>"#;
+            let c = b.cluster(
+                Compression::None,
+                vec![page.to_vec(), b"icon".to_vec(), b"linked".to_vec()],
+            );
+            for (ns, url) in candidates {
+                b.article(*ns, url, "", "image/png", c, 1);
+            }
+            b.article('A', "Main_Page", "", "text/html", c, 0)
+                .article('I', "icon", "", "image/x-icon", c, 2)
+                .main_page('A', "Main_Page");
+            b.open(name)
+        };
+        let png = Some((MimeType::Type("image/png".into()), b"icon".to_vec()));
+
+        let zim = archive(
+            "icon_all",
+            &[
+                ('M', "Illustration_48x48@1"),
+                ('-', "favicon"),
+                ('I', "favicon.png"),
+            ],
+        );
+        assert_eq!(zim.icon().unwrap(), png);
+        let zim = archive("icon_favicon", &[('-', "favicon")]);
+        assert_eq!(zim.icon().unwrap(), png);
+        let zim = archive("icon_legacy", &[('I', "favicon.png")]);
+        assert_eq!(zim.icon().unwrap(), png);
+        let zim = archive("icon_legacy_bare", &[('I', "favicon")]);
+        assert_eq!(zim.icon().unwrap(), png);
+        assert_eq!(zim.illustration(48).unwrap(), Some(b"icon".to_vec()));
+
+        // a `<link rel="icon">` without href doesn't end the search
+        assert_eq!(
+            find_icon_link(r#"<link rel="icon"><link rel="icon" href="a.png">"#),
+            Some("a.png")
+        );
+        let zim = archive("icon_linked", &[]);
+        assert_eq!(
+            zim.icon().unwrap(),
+            Some((MimeType::Type("image/x-icon".into()), b"linked".to_vec()))
+        );
+
+        let zim = test_util::sample(5).open("icon_none");
+        assert_eq!(zim.icon().unwrap(), None);
+    }
+
+    #[test]
+    fn test_get_by_encoded_url() {
+        let zim = test_util::sample(5).open("get_by_encoded_url");
+        let entry = zim.get_by_encoded_url('A', "New%20York").unwrap().unwrap();
+        assert_eq!(entry.url, "New York");
+        assert!(zim.get_by_encoded_url('A', "Berlin").unwrap().is_some());
+        assert!(zim.get_by_encoded_url('A', "New%2").unwrap().is_none());
+
+        let mut b = test_util::ArchiveBuilder::new(5);
+        let c = b.cluster(Compression::None, vec![b"a".to_vec(), b"b".to_vec()]);
+        b.article('A', "100%", "", "text/html", c, 0)
+            .article('A', "Café/x", "", "text/html", c, 1);
+        let zim = b.open("get_by_encoded_url_escapes");
+        let url = |encoded| zim.get_by_encoded_url('A', encoded).unwrap().map(|e| e.url);
+        assert_eq!(url("Caf%C3%a9%2Fx"), Some("Café/x".to_string()));
+        assert_eq!(url("100%"), Some("100%".to_string()));
+        assert_eq!(url("100%25"), Some("100%".to_string()));
+        assert_eq!(url("%FF"), None);
+    }
+
+    #[test]
+    fn test_resolve_link() {
+        assert_eq!(
+            resolve_link('A', "Main_Page", "../I/favicon.png"),
+            Some(('I', "favicon.png".to_string()))
+        );
+        assert_eq!(
+            resolve_link('C', "dir/index.html", "../static/icon.ico?v=2"),
+            Some(('C', "static/icon.ico".to_string()))
+        );
+        assert_eq!(
+            resolve_link('C', "index.html", "./favicon.ico"),
+            Some(('C', "favicon.ico".to_string()))
+        );
+        assert_eq!(
+            resolve_link('A', "Main_Page", "../I/fav%20icon%C3%A9.png"),
+            Some(('I', "fav iconé.png".to_string()))
+        );
+        assert_eq!(resolve_link('A', "Main_Page", "../I/%FF.png"), None);
+        assert_eq!(resolve_link('A', "Main_Page", "../../icon.png"), None);
+        assert_eq!(
+            resolve_link('A', "Main_Page", "https://example.org/icon.png"),
+            None
+        );
+        assert_eq!(
+            find_icon_link(r#"<link rel="stylesheet" href="a.css"><link rel=icon href='b.ico'>"#),
+            Some("b.ico")
+        );
     }
 }
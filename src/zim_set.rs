@@ -0,0 +1,154 @@
+#[cfg(feature = "mmap")]
+use std::path::Path;
+
+use crate::directory_entry::DirectoryEntry;
+use crate::errors::{Error, Result};
+use crate::uuid::Uuid;
+use crate::zim::Zim;
+
+/// Identifies an archive within a `ZimSet`, by position or by UUID.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArchiveId {
+    Index(usize),
+    Uuid(Uuid),
+}
+
+impl From<usize> for ArchiveId {
+    fn from(idx: usize) -> Self {
+        ArchiveId::Index(idx)
+    }
+}
+
+impl From<Uuid> for ArchiveId {
+    fn from(uuid: Uuid) -> Self {
+        ArchiveId::Uuid(uuid)
+    }
+}
+
+/// A set of archives served together, e.g. one per language.
+///
+/// This is a thin wrapper: lookups are delegated to the `Zim` they are meant for.
+pub struct ZimSet {
+    archives: Vec<Zim>,
+}
+
+impl ZimSet {
+    pub fn new(archives: Vec<Zim>) -> Self {
+        ZimSet { archives }
+    }
+
+    /// Opens every file with `Zim::new`, failing if any of them can't be opened.
+    #[cfg(feature = "mmap")]
+    pub fn open_multiple<P: AsRef<Path>>(paths: impl IntoIterator<Item = P>) -> Result<ZimSet> {
+        let archives = paths.into_iter().map(Zim::new).collect::<Result<_>>()?;
+
+        Ok(ZimSet::new(archives))
+    }
+
+    /// The archives, in the order they were added.
+    pub fn archives(&self) -> &[Zim] {
+        &self.archives
+    }
+
+    /// Returns the archive with the given index or UUID.
+    pub fn get(&self, id: impl Into<ArchiveId>) -> Option<&Zim> {
+        match id.into() {
+            ArchiveId::Index(idx) => self.archives.get(idx),
+            ArchiveId::Uuid(uuid) => self.archives.iter().find(|zim| zim.header.uuid == uuid),
+        }
+    }
+
+    /// Looks up an entry in one archive, see `Zim::get_by_url`.
+    ///
+    /// Returns `Error::OutOfBounds` if there is no such archive.
+    pub fn get_by_url(
+        &self,
+        id: impl Into<ArchiveId>,
+        ns: char,
+        url: &str,
+    ) -> Result<Option<DirectoryEntry>> {
+        self.get(id).ok_or(Error::OutOfBounds)?.get_by_url(ns, url)
+    }
+
+    /// Returns up to `limit` article titles starting with `prefix`, from all archives.
+    ///
+    /// The result pairs the index of the archive with the title, and is sorted by title. Every
+    /// archive's titles are scanned with `Zim::titles`, so this takes time linear in the total
+    /// number of articles.
+    pub fn suggest(&self, prefix: &str, limit: usize) -> Vec<(usize, String)> {
+        let mut suggestions: Vec<_> = self
+            .archives
+            .iter()
+            .enumerate()
+            .flat_map(|(idx, zim)| {
+                zim.titles()
+                    .filter(|title| title.starts_with(prefix))
+                    .take(limit)
+                    .map(move |title| (idx, title))
+            })
+            .collect();
+        suggestions.sort_by(|a, b| a.1.cmp(&b.1).then(a.0.cmp(&b.0)));
+        suggestions.truncate(limit);
+
+        suggestions
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cluster::Compression;
+    use crate::test_util::ArchiveBuilder;
+
+    fn archive(uuid: u8, pages: &[&str]) -> ArchiveBuilder {
+        let mut b = ArchiveBuilder::new(5).uuid([uuid; 16]);
+        let blobs = pages.iter().map(|page| page.as_bytes().to_vec()).collect();
+        let c = b.cluster(Compression::None, blobs);
+        for (idx, page) in pages.iter().enumerate() {
+            b.article('A', page, page, "text/html", c, idx as u32);
+        }
+        b
+    }
+
+    #[test]
+    fn test_zim_set() {
+        let set = ZimSet::new(vec![
+            archive(1, &["Berlin", "Bonn"]).open("zim_set_de"),
+            archive(2, &["Boston", "New York"]).open("zim_set_en"),
+        ]);
+        assert_eq!(set.archives().len(), 2);
+
+        let entry = set.get_by_url(0, 'A', "Bonn").unwrap().unwrap();
+        assert_eq!(entry.url, "Bonn");
+        let entry = set
+            .get_by_url(Uuid::new([2; 16]), 'A', "New York")
+            .unwrap()
+            .unwrap();
+        assert_eq!(entry.url, "New York");
+        assert!(set.get_by_url(1, 'A', "Bonn").unwrap().is_none());
+        assert!(matches!(
+            set.get_by_url(2, 'A', "Bonn"),
+            Err(Error::OutOfBounds)
+        ));
+
+        assert_eq!(
+            set.suggest("Bo", 10),
+            vec![(0, "Bonn".to_string()), (1, "Boston".to_string())]
+        );
+        assert_eq!(set.suggest("B", 2).len(), 2);
+    }
+
+    #[cfg(feature = "mmap")]
+    #[test]
+    fn test_open_multiple() {
+        let paths = [
+            archive(1, &["Berlin"]).write("open_multiple_de"),
+            crate::test_util::sample(6).write("open_multiple_sample"),
+        ];
+        let set = ZimSet::open_multiple(&paths).unwrap();
+        assert!(set.get_by_url(0, 'A', "Berlin").unwrap().is_some());
+        assert!(set.get_by_url(1, 'A', "New York").unwrap().is_some());
+
+        assert!(ZimSet::open_multiple(["/nonexistent.zim"]).is_err());
+    }
+}
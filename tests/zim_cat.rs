@@ -0,0 +1,95 @@
+#![cfg(feature = "mmap")]
+
+use std::path::PathBuf;
+use std::process::Command;
+
+/// Writes a minimal uncompressed archive with the article `A/Index` and the redirect `A/Home`.
+fn write_archive(content: &[u8]) -> PathBuf {
+    let mime_list = b"text/html\0\0";
+    let mut entries: Vec<Vec<u8>> = Vec::new();
+    // A/Home, redirecting to URL index 1
+    let mut home = vec![0xff, 0xff, 0, b'A', 0, 0, 0, 0, 1, 0, 0, 0];
+    home.extend_from_slice(b"Home\0\0");
+    entries.push(home);
+    // A/Index, cluster 0 blob 0
+    let mut index = vec![0, 0, 0, b'A', 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+    index.extend_from_slice(b"Index\0\0");
+    entries.push(index);
+
+    let mime_list_pos = 80u64;
+    let url_ptr_pos = mime_list_pos + mime_list.len() as u64;
+    let title_ptr_pos = url_ptr_pos + 8 * entries.len() as u64;
+    let mut entry_pos = title_ptr_pos + 4 * entries.len() as u64;
+    let mut url_ptrs = Vec::new();
+    for entry in &entries {
+        url_ptrs.push(entry_pos);
+        entry_pos += entry.len() as u64;
+    }
+    let cluster_ptr_pos = entry_pos;
+    let cluster_pos = cluster_ptr_pos + 8;
+    let mut cluster = vec![1];
+    cluster.extend_from_slice(&8u32.to_le_bytes());
+    cluster.extend_from_slice(&(8 + content.len() as u32).to_le_bytes());
+    cluster.extend_from_slice(content);
+    let checksum_pos = cluster_pos + cluster.len() as u64;
+
+    let mut data = Vec::new();
+    data.extend_from_slice(&72173914u32.to_le_bytes());
+    data.extend_from_slice(&5u16.to_le_bytes());
+    data.extend_from_slice(&0u16.to_le_bytes());
+    data.extend_from_slice(&[0; 16]);
+    data.extend_from_slice(&(entries.len() as u32).to_le_bytes());
+    data.extend_from_slice(&1u32.to_le_bytes());
+    for pos in [url_ptr_pos, title_ptr_pos, cluster_ptr_pos, mime_list_pos] {
+        data.extend_from_slice(&pos.to_le_bytes());
+    }
+    data.extend_from_slice(&1u32.to_le_bytes());
+    data.extend_from_slice(&u32::MAX.to_le_bytes());
+    data.extend_from_slice(&checksum_pos.to_le_bytes());
+    data.extend_from_slice(mime_list);
+    for ptr in url_ptrs {
+        data.extend_from_slice(&ptr.to_le_bytes());
+    }
+    for idx in 0..entries.len() as u32 {
+        data.extend_from_slice(&idx.to_le_bytes());
+    }
+    for entry in entries {
+        data.extend_from_slice(&entry);
+    }
+    data.extend_from_slice(&cluster_pos.to_le_bytes());
+    data.extend_from_slice(&cluster);
+    data.extend_from_slice(&[0; 16]);
+    assert_eq!(data.len() as u64, checksum_pos + 16);
+
+    let path = std::env::temp_dir().join(format!("zim-cat-test-{}.zim", std::process::id()));
+    std::fs::write(&path, data).unwrap();
+    path
+}
+
+fn zim_cat(archive: &PathBuf, path: &str) -> std::process::Output {
+    Command::new(env!("CARGO_BIN_EXE_zim-cat"))
+        .arg(archive)
+        .arg(path)
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn test_zim_cat() {
+    let content = b"<html>Index</html>\n\0\xff";
+    let archive = write_archive(content);
+
+    let output = zim_cat(&archive, "A/Index");
+    assert!(output.status.success());
+    assert_eq!(output.stdout, content);
+
+    let output = zim_cat(&archive, "A/Home");
+    assert!(output.status.success());
+    assert_eq!(output.stdout, content);
+
+    let output = zim_cat(&archive, "A/Missing");
+    assert_eq!(output.status.code(), Some(1));
+    assert!(output.stdout.is_empty());
+
+    std::fs::remove_file(archive).unwrap();
+}